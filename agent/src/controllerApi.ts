@@ -1,14 +1,17 @@
 import axios, { Axios, AxiosResponse } from 'axios';
 import {
   Agent,
+  ContainerKind,
   Operation,
   Vec3,
   Item,
   Hold,
   Vec2,
   Dimension,
+  Direction,
   Location,
   OperationStatus,
+  PathEdge,
   PfResultNode
 } from './types';
 
@@ -83,6 +86,7 @@ export const inventoryScanned = async (
   slots: Array<Item | null>,
   inventoryLocation: Location,
   openFrom: Vec3,
+  kind: ContainerKind,
   agent: Agent
 ): Promise<AxiosResponse<string>> =>
   axios.post(
@@ -90,7 +94,8 @@ export const inventoryScanned = async (
     {
       location: inventoryLocation,
       slots,
-      open_from: openFrom
+      open_from: openFrom,
+      kind
     },
     { headers: agentHeader(agent) }
   );
@@ -126,6 +131,8 @@ type PathfindingResponse =
   | {
       type: 'PathFound';
       path: PfResultNode[];
+      // Graph edges covered getting to each path node
+      edges: PathEdge[][];
     }
   | {
       type: 'Error';
@@ -147,9 +154,40 @@ export const findPath = async (
     }
   );
 
+export const pathFailed = async (
+  agent: Agent,
+  edges: PathEdge[]
+): Promise<AxiosResponse<string>> =>
+  axios.post(
+    agentEndpoint('path_failed'),
+    {
+      edges
+    },
+    {
+      headers: agentHeader(agent)
+    }
+  );
+
+export const segmentTraveled = async (
+  agent: Agent,
+  edges: PathEdge[],
+  seconds: number
+): Promise<AxiosResponse<string>> =>
+  axios.post(
+    agentEndpoint('segment_traveled'),
+    {
+      edges,
+      seconds
+    },
+    {
+      headers: agentHeader(agent)
+    }
+  );
+
 export type Sign = {
   lines: string[];
   location: Location;
+  facing?: Direction;
 };
 
 export type ScanRegion = {
@@ -19,7 +19,10 @@ import {
   importInventory,
   scanSigns,
   unloadShulker,
-  loadShulker
+  loadShulker,
+  craft,
+  smelt,
+  collectSmelted
 } from './operations';
 import { navigateTo, sendVisibleSignData } from './operations/procedures';
 import { clearInventory, sleep } from './utils';
@@ -112,6 +115,12 @@ const main = async () => {
           await unloadShulker(operation.kind, bot, agent);
         } else if (operation.kind.type === 'LoadShulker') {
           await loadShulker(operation.kind, bot, agent);
+        } else if (operation.kind.type === 'Craft') {
+          await craft(operation.kind, bot, agent);
+        } else if (operation.kind.type === 'Smelt') {
+          await smelt(operation.kind, bot, agent);
+        } else if (operation.kind.type === 'CollectSmelted') {
+          await collectSmelted(operation.kind, bot, agent);
         } else {
           throw new Error('Unknown operation kind dispatched!');
         }
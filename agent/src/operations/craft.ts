@@ -0,0 +1,125 @@
+import assert from 'assert';
+import { Bot, Chest } from 'mineflayer';
+import { Window } from 'prismarine-windows';
+import { setTimeout } from 'timers/promises';
+import vec3 from 'vec3';
+
+import { getHold } from '../controllerApi';
+import { Agent, CraftOperationKind, Location, locEq, Vec3 } from '../types';
+import {
+  navigateTo,
+  openChestAt,
+  sendChestData,
+  storeResults,
+  transferItems
+} from './procedures';
+
+// The server fills the result slot once the grid makes a recipe
+const waitForResult = async (table: Window) => {
+  for (let tries = 0; !table.slots[0] && tries < 20; tries++) {
+    await setTimeout(100);
+  }
+};
+
+export const craft = async (
+  {
+    crafting_table_location,
+    node_location,
+    recipe_source_holds,
+    destination_holds,
+    item_id,
+    crafts
+  }: CraftOperationKind,
+  bot: Bot,
+  agent: Agent
+) => {
+  // A hold feeds every grid cell it appears in, so take enough for all of them
+  const neededByHold = new Map<string, number>();
+  for (const holdId of recipe_source_holds) {
+    if (!holdId) continue;
+    neededByHold.set(holdId, (neededByHold.get(holdId) || 0) + crafts);
+  }
+  const invSlotByHold = new Map<string, number>();
+
+  let lastChest: {
+    location: Location;
+    chest: Chest & Window;
+    openFrom: Vec3;
+  } | null = null;
+
+  for (const [invSlot, [holdId, count]] of Array.from(
+    neededByHold.entries()
+  ).entries()) {
+    const {
+      data: {
+        hold: { location: sourceLocation, slot: sourceSlot, open_from }
+      }
+    } = await getHold(holdId, agent);
+
+    if (lastChest && !locEq(sourceLocation, lastChest.location)) {
+      await sendChestData(
+        bot,
+        lastChest.chest,
+        lastChest.location,
+        lastChest.openFrom,
+        agent
+      );
+      lastChest.chest.close();
+      lastChest = null;
+    }
+
+    const chest: Chest & Window =
+      lastChest?.chest ||
+      (await openChestAt(sourceLocation, open_from, bot, agent));
+
+    await transferItems(bot, chest, sourceSlot, invSlot, count, 'from_chest');
+    invSlotByHold.set(holdId, invSlot);
+
+    lastChest = { chest, location: sourceLocation, openFrom: open_from };
+  }
+
+  if (lastChest) {
+    await sendChestData(
+      bot,
+      lastChest.chest,
+      lastChest.location,
+      lastChest.openFrom,
+      agent
+    );
+    lastChest.chest.close();
+  }
+
+  // Craft
+  await navigateTo(node_location, bot, agent);
+
+  const craftingTable = bot.blockAt(vec3(crafting_table_location));
+  assert(craftingTable, 'Crafting table is not loaded');
+
+  const table: Chest & Window = await bot.openBlock(craftingTable);
+
+  try {
+    // Lay out the grid the operator planned, cell by cell, slot 0 being the result
+    for (const [cell, holdId] of recipe_source_holds.entries()) {
+      if (!holdId) continue;
+
+      const invSlot = invSlotByHold.get(holdId);
+      assert(invSlot !== undefined, 'Items for grid cell were not gathered');
+
+      await transferItems(bot, table, cell + 1, invSlot, crafts, 'to_chest');
+    }
+
+    for (let craft = 0; craft < crafts; craft++) {
+      await waitForResult(table);
+      assert(
+        table.slots[0]?.type === item_id,
+        `Grid does not craft item ${item_id}`
+      );
+
+      await bot.putAway(0);
+    }
+  } finally {
+    table.close();
+  }
+
+  await storeResults(bot, item_id, destination_holds, agent);
+};
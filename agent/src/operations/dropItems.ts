@@ -32,6 +32,7 @@ export const dropItems = async (
 
     if (lastChest && !locEq(sourceLocation, lastChest.location)) {
       await sendChestData(
+        bot,
         lastChest.chest,
         lastChest.location,
         lastChest.openFrom,
@@ -59,6 +60,7 @@ export const dropItems = async (
 
   if (lastChest) {
     await sendChestData(
+      bot,
       lastChest.chest,
       lastChest.location,
       lastChest.openFrom,
@@ -80,6 +80,7 @@ export const importInventory = async (
 
     if (lastChest && !locEq(destinationLocation, lastChest.location)) {
       await sendChestData(
+        bot,
         lastChest.chest,
         lastChest.location,
         lastChest.openFrom,
@@ -111,6 +112,7 @@ export const importInventory = async (
 
   if (lastChest) {
     await sendChestData(
+      bot,
       lastChest.chest,
       lastChest.location,
       lastChest.openFrom,
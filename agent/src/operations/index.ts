@@ -5,3 +5,5 @@ export * from './importInventory';
 export * from './scanSigns';
 export * from './unloadShulker';
 export * from './loadShulker';
+export * from './craft';
+export * from './smelt';
@@ -47,6 +47,7 @@ export const loadShulker = async (
   );
   await transferItems(bot, shulkerChest, shulkerChestSlot, 27, 1, 'from_chest');
   await sendChestData(
+    bot,
     shulkerChest,
     shulkerChestLocation,
     shulkerOpenFrom,
@@ -75,6 +76,7 @@ export const loadShulker = async (
 
     if (lastChest && !locEq(sourceLocation, lastChest.location)) {
       await sendChestData(
+        bot,
         lastChest.chest,
         lastChest.location,
         lastChest.openFrom,
@@ -102,6 +104,7 @@ export const loadShulker = async (
 
   if (lastChest) {
     await sendChestData(
+      bot,
       lastChest.chest,
       lastChest.location,
       lastChest.openFrom,
@@ -167,6 +170,7 @@ export const loadShulker = async (
   );
   await transferItems(bot, shulkerChest, shulkerChestSlot, 27, 1, 'to_chest');
   await sendChestData(
+    bot,
     shulkerChest,
     shulkerChestLocation,
     shulkerOpenFrom,
@@ -31,6 +31,7 @@ export const moveItems = async (
   for (const [idx, hold] of sourceHolds.entries()) {
     if (lastChest && !locEq(hold.location, lastChest.location)) {
       await sendChestData(
+        bot,
         lastChest.chest,
         lastChest.location,
         lastChest.openFrom,
@@ -63,6 +64,7 @@ export const moveItems = async (
   for (const [idx, hold] of destinationHolds.entries()) {
     if (lastChest && !locEq(hold.location, lastChest.location)) {
       await sendChestData(
+        bot,
         lastChest.chest,
         lastChest.location,
         lastChest.openFrom,
@@ -94,6 +96,7 @@ export const moveItems = async (
 
   if (lastChest) {
     await sendChestData(
+      bot,
       lastChest.chest,
       lastChest.location,
       lastChest.openFrom,
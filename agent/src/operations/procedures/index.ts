@@ -4,3 +4,4 @@ export * from './sendChestData';
 export * from './transferItems';
 export * from './dropSlot';
 export * from './signScanner';
+export * from './storeResults';
@@ -1,6 +1,10 @@
 import { Bot } from 'mineflayer';
 import vec3, { Vec3 as depVec3 } from 'vec3';
-import { findPath } from '../../controllerApi';
+import {
+  findPath,
+  pathFailed,
+  segmentTraveled
+} from '../../controllerApi';
 import { setTimeout } from 'timers/promises';
 
 import { Agent, Vec3, vecEq, Location, stringToDim } from '../../types';
@@ -104,12 +108,36 @@ const navigateToImpl = async (
 
   if (pathResp.type === 'Error') throw new Error('Pathfinding request failed!');
 
-  for (const node of pathResp.path) {
+  for (const [idx, node] of pathResp.path.entries()) {
     console.log('NAV: Navigating to next node', node);
-    if ('Vec' in node) {
-      await flyTo(bot, vec3(node.Vec).add(vec3({ x: 0.5, y: 0, z: 0.5 })));
-    } else {
-      await takePortal(node.Portal.vec, bot);
+    const edges = pathResp.edges[idx] || [];
+    const segmentStart = Date.now();
+
+    try {
+      if ('Vec' in node) {
+        await flyTo(bot, vec3(node.Vec).add(vec3({ x: 0.5, y: 0, z: 0.5 })));
+
+        if (!vecEq(floorVec3(bot.player.entity.position), node.Vec))
+          throw new Error('Did not arrive at next node');
+      } else {
+        await takePortal(node.Portal.vec, bot);
+      }
+    } catch (err) {
+      // Let the controller route around these edges on the next attempt
+      if (edges.length > 0) {
+        await pathFailed(agent, edges).catch((reportErr) =>
+          console.warn('NAV: Failed to report blocked segment', reportErr)
+        );
+      }
+
+      throw err;
+    }
+
+    if (edges.length > 0) {
+      const seconds = (Date.now() - segmentStart) / 1000;
+      segmentTraveled(agent, edges, seconds).catch((reportErr) =>
+        console.warn('NAV: Failed to report segment time', reportErr)
+      );
     }
   }
 
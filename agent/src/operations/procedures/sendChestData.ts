@@ -1,9 +1,58 @@
-import { Chest } from 'mineflayer';
+import { Bot, Chest } from 'mineflayer';
 import { Window } from 'prismarine-windows';
+import vec3 from 'vec3';
 import { inventoryScanned } from '../../controllerApi';
-import { Agent, Item, Location, Vec3 } from '../../types';
+import { Agent, ContainerKind, Item, Location, Vec3 } from '../../types';
+
+// Chest halves connect clockwise from their facing when left, counter-clockwise when right
+const connectedChestOffsets: {
+  [facing: string]: { left: Vec3; right: Vec3 };
+} = {
+  north: { left: { x: 1, y: 0, z: 0 }, right: { x: -1, y: 0, z: 0 } },
+  east: { left: { x: 0, y: 0, z: 1 }, right: { x: 0, y: 0, z: -1 } },
+  south: { left: { x: -1, y: 0, z: 0 }, right: { x: 1, y: 0, z: 0 } },
+  west: { left: { x: 0, y: 0, z: -1 }, right: { x: 0, y: 0, z: 1 } }
+};
+
+const containerKind = (
+  bot: Bot,
+  location: Location,
+  slotCount: number
+): ContainerKind => {
+  const block = bot.blockAt(vec3(location.vec3));
+
+  if (block?.name === 'barrel') return 'Barrel';
+  if (block?.name.endsWith('shulker_box')) return 'ShulkerBox';
+
+  if (block?.name === 'chest' || block?.name === 'trapped_chest') {
+    const { type, facing } = block.getProperties() as {
+      type: string;
+      facing: string;
+    };
+
+    if (type === 'single') return 'Chest';
+
+    const offset =
+      connectedChestOffsets[facing]?.[type === 'left' ? 'left' : 'right'];
+
+    if (offset) {
+      return {
+        DoubleChest: {
+          other_half: {
+            x: location.vec3.x + offset.x,
+            y: location.vec3.y + offset.y,
+            z: location.vec3.z + offset.z
+          }
+        }
+      };
+    }
+  }
+
+  return { Other: { slot_count: slotCount } };
+};
 
 export const sendChestData = async (
+  bot: Bot,
   chest: Chest & Window,
   location: Location,
   open_from: Vec3,
@@ -23,5 +72,7 @@ export const sendChestData = async (
       };
     });
 
-  await inventoryScanned(items, location, open_from, agent);
+  const kind = containerKind(bot, location, chest.inventoryStart);
+
+  await inventoryScanned(items, location, open_from, kind, agent);
 };
@@ -1,5 +1,5 @@
 import { Bot } from 'mineflayer';
-import { Agent, stringToDim, Vec3 } from '../../types';
+import { Agent, Direction, stringToDim, Vec3 } from '../../types';
 import vec3 from 'vec3';
 import { ScanRegion, sendSignScanData, Sign } from '../../controllerApi';
 import { setTimeout } from 'timers';
@@ -64,6 +64,31 @@ const parseLine = (line: any): string => {
   return valParsed['text'];
 };
 
+const OPPOSITE_DIRECTIONS: Record<Direction, Direction> = {
+  North: 'South',
+  East: 'West',
+  South: 'North',
+  West: 'East'
+};
+
+// Standing signs have 16 rotations starting from south, wall signs face a direction
+const ROTATION_DIRECTIONS: Direction[] = ['South', 'West', 'North', 'East'];
+
+const getSignFacing = (bot: Bot, vec: Vec3): Direction | undefined => {
+  const properties: any = bot.blockAt(vec3(vec))?.getProperties();
+
+  if (typeof properties?.facing === 'string') {
+    const facing = properties.facing;
+    return (facing.charAt(0).toUpperCase() + facing.slice(1)) as Direction;
+  }
+
+  if (properties?.rotation !== undefined) {
+    return ROTATION_DIRECTIONS[Math.round(Number(properties.rotation) / 4) % 4];
+  }
+
+  return undefined;
+};
+
 const getSignsInChunk = (
   bot: Bot,
   chunkPos: Vec3,
@@ -98,7 +123,8 @@ const getSignsInChunk = (
       ];
       signs.push({
         location: { vec3: vec, dim: stringToDim(bot.game.dimension) },
-        lines
+        lines,
+        facing: getSignFacing(bot, vec)
       });
     }
 
@@ -115,9 +141,11 @@ const getSignsInChunk = (
         parseLine(backTextMessages[2]),
         parseLine(backTextMessages[3])
       ];
+      const frontFacing = getSignFacing(bot, vec);
       signs.push({
         location: { vec3: vec, dim: stringToDim(bot.game.dimension) },
-        lines
+        lines,
+        facing: frontFacing && OPPOSITE_DIRECTIONS[frontFacing]
       });
     }
   }
@@ -0,0 +1,90 @@
+import assert from 'assert';
+import { Bot, Chest } from 'mineflayer';
+import { Window } from 'prismarine-windows';
+
+import { getHold } from '../../controllerApi';
+import { Agent, Location, locEq, Vec3 } from '../../types';
+import { openChestAt } from './openChestAt';
+import { sendChestData } from './sendChestData';
+import { transferItems } from './transferItems';
+
+// Stores every stack of the item in the inventory, one stack per destination hold
+export const storeResults = async (
+  bot: Bot,
+  itemId: number,
+  destinationHolds: string[],
+  agent: Agent
+) => {
+  const resultSlots = bot.inventory.slots
+    .map((item, slot) => ({ item, slot }))
+    .filter(
+      ({ item, slot }) =>
+        item?.type === itemId && slot >= bot.inventory.inventoryStart
+    )
+    .map(({ slot }) => slot - bot.inventory.inventoryStart);
+
+  assert(
+    resultSlots.length <= destinationHolds.length,
+    'More result stacks than destination holds'
+  );
+
+  let lastChest: {
+    location: Location;
+    chest: Chest & Window;
+    openFrom: Vec3;
+  } | null = null;
+
+  for (const [idx, invSlot] of resultSlots.entries()) {
+    const {
+      data: {
+        hold: {
+          location: destinationLocation,
+          slot: destinationSlot,
+          open_from
+        }
+      }
+    } = await getHold(destinationHolds[idx], agent);
+
+    if (lastChest && !locEq(destinationLocation, lastChest.location)) {
+      await sendChestData(
+        bot,
+        lastChest.chest,
+        lastChest.location,
+        lastChest.openFrom,
+        agent
+      );
+      lastChest.chest.close();
+      lastChest = null;
+    }
+
+    const chest: Chest & Window =
+      lastChest?.chest ||
+      (await openChestAt(destinationLocation, open_from, bot, agent));
+
+    await transferItems(
+      bot,
+      chest,
+      destinationSlot,
+      invSlot,
+      Infinity,
+      'to_chest'
+    );
+
+    lastChest = {
+      chest,
+      location: destinationLocation,
+      openFrom: open_from
+    };
+  }
+
+  if (lastChest) {
+    await sendChestData(
+      bot,
+      lastChest.chest,
+      lastChest.location,
+      lastChest.openFrom,
+      agent
+    );
+    lastChest.chest.close();
+  }
+};
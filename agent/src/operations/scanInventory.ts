@@ -16,6 +16,7 @@ export const scanInventory = async (
   );
 
   await sendChestData(
+    bot,
     chest,
     operationKind.location,
     operationKind.open_from,
@@ -0,0 +1,94 @@
+import assert from 'assert';
+import { Bot } from 'mineflayer';
+import vec3 from 'vec3';
+
+import { getHold } from '../controllerApi';
+import {
+  Agent,
+  CollectSmeltedOperationKind,
+  SmeltOperationKind
+} from '../types';
+import {
+  navigateTo,
+  openChestAt,
+  sendChestData,
+  storeResults,
+  transferItems
+} from './procedures';
+
+export const smelt = async (
+  {
+    furnace_location,
+    node_location,
+    input_hold,
+    input_count,
+    fuel_hold,
+    fuel_count
+  }: SmeltOperationKind,
+  bot: Bot,
+  agent: Agent
+) => {
+  // Input goes to inventory slot 0, fuel to slot 1
+  for (const [invSlot, [holdId, count]] of [
+    [input_hold, input_count] as const,
+    [fuel_hold, fuel_count] as const
+  ].entries()) {
+    const {
+      data: {
+        hold: { location, slot, open_from }
+      }
+    } = await getHold(holdId, agent);
+
+    const chest = await openChestAt(location, open_from, bot, agent);
+    await transferItems(bot, chest, slot, invSlot, count, 'from_chest');
+    await sendChestData(bot, chest, location, open_from, agent);
+    chest.close();
+  }
+
+  const input = bot.inventory.slots[bot.inventory.inventoryStart];
+  const fuel = bot.inventory.slots[bot.inventory.inventoryStart + 1];
+  assert(input && fuel, 'Input or fuel missing from inventory');
+
+  await navigateTo(node_location, bot, agent);
+
+  const furnaceBlock = bot.blockAt(vec3(furnace_location));
+  assert(furnaceBlock, 'Furnace is not loaded');
+
+  const furnace = await bot.openFurnace(furnaceBlock);
+
+  try {
+    await furnace.putInput(input.type, null, input_count);
+    await furnace.putFuel(fuel.type, null, fuel_count);
+  } finally {
+    furnace.close();
+  }
+};
+
+export const collectSmelted = async (
+  {
+    furnace_location,
+    node_location,
+    destination_holds,
+    item_id
+  }: CollectSmeltedOperationKind,
+  bot: Bot,
+  agent: Agent
+) => {
+  await navigateTo(node_location, bot, agent);
+
+  const furnaceBlock = bot.blockAt(vec3(furnace_location));
+  assert(furnaceBlock, 'Furnace is not loaded');
+
+  const furnace = await bot.openFurnace(furnaceBlock);
+
+  try {
+    // Furnaces stop while their chunk is unloaded, the operator tries again later
+    assert(!furnace.inputItem(), 'Furnace is still smelting');
+    assert(furnace.outputItem(), 'Furnace has nothing to collect');
+    await furnace.takeOutput();
+  } finally {
+    furnace.close();
+  }
+
+  await storeResults(bot, item_id, destination_holds, agent);
+};
@@ -47,6 +47,7 @@ export const unloadShulker = async (
   );
   await transferItems(bot, shulkerChest, shulkerChestSlot, 27, 1, 'from_chest');
   await sendChestData(
+    bot,
     shulkerChest,
     shulkerChestLocation,
     shulkerOpenFrom,
@@ -119,6 +120,7 @@ export const unloadShulker = async (
   );
   await transferItems(bot, shulkerChest, shulkerChestSlot, 27, 1, 'to_chest');
   await sendChestData(
+    bot,
     shulkerChest,
     shulkerChestLocation,
     shulkerOpenFrom,
@@ -149,6 +151,7 @@ export const unloadShulker = async (
 
     if (lastChest && !locEq(destinationLocation, lastChest.location)) {
       await sendChestData(
+        bot,
         lastChest.chest,
         lastChest.location,
         lastChest.openFrom,
@@ -180,6 +183,7 @@ export const unloadShulker = async (
 
   if (lastChest) {
     await sendChestData(
+      bot,
       lastChest.chest,
       lastChest.location,
       lastChest.openFrom,
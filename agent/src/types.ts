@@ -22,6 +22,11 @@ export type PfResultNode =
       };
     };
 
+export type PathEdge = {
+  source_node: string;
+  destination_node: string;
+};
+
 export const stringToDim = (dim: string): Dimension => {
   if (dim === 'overworld') {
     return 'Overworld';
@@ -59,6 +64,15 @@ export const locEq = (a: Location, b: Location) =>
 
 export type Vec2 = Omit<Vec3, 'y'>;
 
+export type Direction = 'North' | 'East' | 'South' | 'West';
+
+export type ContainerKind =
+  | 'Chest'
+  | 'Barrel'
+  | 'ShulkerBox'
+  | { DoubleChest: { other_half: Vec3 } }
+  | { Other: { slot_count: number } };
+
 export type ScanInventoryOperationKind = {
   type: 'ScanInventory';
   location: Location;
@@ -92,6 +106,34 @@ export type ImportInventoryOperationKind = {
   destination_holds: string[];
 };
 
+export type CraftOperationKind = {
+  type: 'Craft';
+  crafting_table_location: Vec3;
+  node_location: Location;
+  recipe_source_holds: (string | null)[];
+  destination_holds: string[];
+  item_id: number;
+  crafts: number;
+};
+
+export type SmeltOperationKind = {
+  type: 'Smelt';
+  furnace_location: Vec3;
+  node_location: Location;
+  input_hold: string;
+  input_count: number;
+  fuel_hold: string;
+  fuel_count: number;
+};
+
+export type CollectSmeltedOperationKind = {
+  type: 'CollectSmelted';
+  furnace_location: Vec3;
+  node_location: Location;
+  destination_holds: string[];
+  item_id: number;
+};
+
 export type LoadShulkerOperationKind = {
   type: 'LoadShulker';
   shulker_station_location: Location;
@@ -112,6 +154,9 @@ export type OperationKind =
   | MoveItemsOperationKind
   | DropItemsOperationKind
   | ImportInventoryOperationKind
+  | CraftOperationKind
+  | SmeltOperationKind
+  | CollectSmeltedOperationKind
   | UnloadShulkerOperationKind
   | LoadShulkerOperationKind;
 
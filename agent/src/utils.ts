@@ -45,7 +45,7 @@ export const clearInventory = async (
       'to_chest'
     );
 
-    await sendChestData(chest, location, open_from, agent);
+    await sendChestData(bot, chest, location, open_from, agent);
 
     chest.close();
 
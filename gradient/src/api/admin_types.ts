@@ -13,4 +13,13 @@ export type Stats = {
   agents_connected: number;
 
   services_tick_times_micros: { [service: string]: number };
+
+  path_cache: PathCacheStats;
+};
+
+export type PathCacheStats = {
+  hits: number;
+  misses: number;
+  hit_rate: number | null;
+  cached_paths: number;
 };
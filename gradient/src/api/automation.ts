@@ -8,6 +8,13 @@ import {
   CompiledSignConfig,
   HoldMatchResult,
   HoldRequestFilter,
+  NodeMatch,
+  NodeQuery,
+  QueuedCrafts,
+  CraftPlan,
+  QueuedPlan,
+  SmeltJob,
+  FurnaceLoad,
 } from './automation_types';
 import { Item } from './types';
 
@@ -27,6 +34,16 @@ export const getInventoryListing = (): Promise<AxiosResponse<Array<Item>>> =>
 export const getSignConfig = (): Promise<AxiosResponse<CompiledSignConfig>> =>
   axios.get(endpoint('sign_config'), { headers });
 
+export const getNodes = (
+  params: NodeQuery,
+): Promise<AxiosResponse<{ nodes: NodeMatch[] }>> =>
+  axios.get(endpoint('nodes'), { headers, params });
+
+export const getNearestNode = (
+  params: NodeQuery,
+): Promise<AxiosResponse<NodeMatch>> =>
+  axios.get(endpoint('nodes/nearest'), { headers, params });
+
 export const getHolds = (): Promise<AxiosResponse<{ holds: Hold[] }>> =>
   axios.get(endpoint('holds'), { headers });
 
@@ -54,6 +71,48 @@ export const createOperation = (
 ): Promise<AxiosResponse<{ operation: Operation }>> =>
   axios.post(endpoint('operations'), { kind, priority }, { headers });
 
+export const craftItem = (
+  item: string,
+  count: number,
+  priority?: OperationPriority,
+): Promise<AxiosResponse<QueuedCrafts>> =>
+  axios.post(endpoint('craft'), { item, count, priority }, { headers });
+
+export const planCraft = (
+  item: string,
+  count: number,
+): Promise<AxiosResponse<CraftPlan>> =>
+  axios.post(endpoint('craft/plan'), { item, count }, { headers });
+
+export const queueCraftPlan = (
+  item: string,
+  count: number,
+  priority?: OperationPriority,
+): Promise<AxiosResponse<QueuedPlan>> =>
+  axios.post(
+    endpoint('craft/plan'),
+    { item, count, execute: true, priority },
+    { headers },
+  );
+
+export const decompressItem = (
+  item_id: number,
+  count: number,
+  priority?: OperationPriority,
+): Promise<AxiosResponse<QueuedCrafts>> =>
+  axios.post(endpoint('decompress'), { item_id, count, priority }, { headers });
+
+export const smeltItem = (
+  item: string,
+  count: number,
+  priority?: OperationPriority,
+): Promise<AxiosResponse<SmeltJob>> =>
+  axios.post(endpoint('smelt'), { item, count, priority }, { headers });
+
+export const getSmelting = (): Promise<
+  AxiosResponse<{ jobs: SmeltJob[]; loads: FurnaceLoad[] }>
+> => axios.get(endpoint('smelting'), { headers });
+
 export const getOperation = (
   operation_id: string,
 ): Promise<AxiosResponse<{ operation: Operation }>> =>
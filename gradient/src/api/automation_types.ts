@@ -13,13 +13,33 @@ export type PathfindingNode = {
   name: string;
   pickup?: Vec3;
   dropoff?: Vec3;
+  crafting_table?: Vec3;
+  furnaces: Vec3[];
+  tags: string[];
 };
 
+export type NodeRole = 'pickup' | 'drop_off' | 'shulker_station';
+
+export type NodeQuery = {
+  tag?: string;
+  role?: NodeRole;
+  from?: string;
+  from_hold?: string;
+};
+
+export type NodeMatch = PathfindingNode & {
+  cost: number | null;
+};
+
+export type Direction = 'North' | 'East' | 'South' | 'West';
+
 export type StorageComplex = {
   FlatFloor: {
     dimension: Dimension;
     y_level: number;
     bounds: [Vec2, Vec2];
+    layers: number;
+    stride: number;
     name: string;
   };
   Tower: {
@@ -27,24 +47,80 @@ export type StorageComplex = {
     origin: Vec3;
     name: string;
     height: number;
+    radius: number;
+    skipped_layers: number[];
+  };
+  Wall: {
+    dimension: Dimension;
+    name: string;
+    origin: Vec3;
+    width: number;
+    height: number;
+    facing: Direction;
+    standoff: number;
   };
 };
 
-type SignParseError = {
-  type: 'NoMarker' | 'OffsetParseFailed' | 'UnknownSignType' | 'NameEmpty';
+type SignDiagnostic = {
+  message: string;
+  location: Loc;
+  lines: [string, string, string, string];
+  line: number;
+  suggestion: string | null;
 };
 
-type SignValidationError =
-  | {
-      type: 'DuplicatePathfindingNode';
-      name: string;
-    }
-  | {
-      type: 'UnknownNode';
-      name: string;
-    };
+type SignParseError = SignDiagnostic & {
+  type:
+    | 'NoMarker'
+    | 'OffsetParseFailed'
+    | 'UnknownFacing'
+    | 'UnknownSignType'
+    | 'NameEmpty'
+    | 'BadHeight'
+    | 'BadWallLayout'
+    | 'BadLinkOptions'
+    | 'BadTowerLayout'
+    | 'BadFloorLayout'
+    | 'SideOpenedFloorTooDeep'
+    | 'NoTags';
+};
+
+type SignValidationError = SignDiagnostic &
+  (
+    | {
+        type: 'DuplicatePathfindingNode';
+        name: string;
+      }
+    | {
+        type: 'UnknownNode';
+        name: string;
+      }
+    | {
+        type: 'LinkAcrossDimensions' | 'PortalWithoutReturn';
+        source_node: string;
+        destination_node: string;
+      }
+    | {
+        type:
+          | 'DuplicatePickup'
+          | 'DuplicateDropOff'
+          | 'DuplicatePortal'
+          | 'DuplicateCraftingStation'
+          | 'UnreachableNode'
+          | 'NoReturnPath'
+          | 'DisconnectedNode'
+          | 'NoShulkerStation';
+        name: string;
+      }
+    | {
+        type: 'OverlappingComplexes';
+        name: string;
+        other_name: string;
+      }
+  );
 
 export type CompiledSignConfig = {
+  generation: number;
   nodes: { [name: string]: PathfindingNode };
   complexes: { [name: string]: StorageComplex };
 
@@ -128,16 +204,139 @@ export type ImportInventoryOperationKind = {
   destination_holds: string[];
 };
 
+export type CraftOperationKind = {
+  type: 'Craft';
+  crafting_table_location: Vec3;
+  node_location: Loc;
+  recipe_source_holds: (string | null)[];
+  destination_holds: string[];
+  item_id: number;
+  crafts: number;
+};
+
+export type SmeltOperationKind = {
+  type: 'Smelt';
+  furnace_location: Vec3;
+  node_location: Loc;
+  input_hold: string;
+  input_count: number;
+  fuel_hold: string;
+  fuel_count: number;
+};
+
+export type CollectSmeltedOperationKind = {
+  type: 'CollectSmelted';
+  furnace_location: Vec3;
+  node_location: Loc;
+  destination_holds: string[];
+  item_id: number;
+};
+
 export type OperationKind =
   | ScanInventoryOperationKind
   | ScanSignsOperationKind
   | MoveItemsOperationKind
   | DropItemsOperationKind
-  | ImportInventoryOperationKind;
+  | ImportInventoryOperationKind
+  | CraftOperationKind
+  | SmeltOperationKind
+  | CollectSmeltedOperationKind;
+
+export type CraftError =
+  | { type: 'UnknownItem'; name: string }
+  | { type: 'NoRecipe'; name: string }
+  | {
+      type: 'MissingIngredient';
+      name: string;
+      needed: number;
+      available: number;
+    }
+  | { type: 'NoCraftingStation' }
+  | { type: 'NoSpace' };
+
+export type QueuedCrafts = {
+  operations: string[];
+  crafted: number;
+};
+
+export type SmeltError =
+  | { type: 'NoRecipe'; name: string }
+  | {
+      type: 'MissingInput';
+      name: string;
+      needed: number;
+      available: number;
+    }
+  | { type: 'NoFurnaceStation' };
+
+export type SmeltJob = {
+  id: string;
+  input_id: number;
+  result_id: number;
+  remaining: number;
+  priority: OperationPriority;
+  alerted: boolean;
+  predicted_completion_at: string | null;
+};
+
+export type FurnaceLoad = {
+  job_id: string;
+  furnace: Loc;
+  input_id: number;
+  node_location: Loc;
+  result_id: number;
+  count: number;
+  priority: OperationPriority;
+  smelt_operation: string;
+  ready_at: string | null;
+  collect_operation: string | null;
+  predicted_ready_at: string | null;
+};
+
+export type Recipe = {
+  grid: (number | null)[];
+  result_id: number;
+  result_count: number;
+};
+
+export type ItemCount = {
+  item_id: number;
+  name: string;
+  count: number;
+};
+
+export type PlanNode = {
+  item_id: number;
+  name: string;
+  needed: number;
+  from_storage: number;
+  craft: { recipe: Recipe; crafts: number; ingredients: PlanNode[] } | null;
+  missing: number;
+};
+
+export type CraftPlan = {
+  tree: PlanNode;
+  steps: {
+    item_id: number;
+    name: string;
+    recipe: Recipe;
+    crafts: number;
+    batches: number;
+  }[];
+  consumed: ItemCount[];
+  missing: ItemCount[];
+};
+
+export type QueuedPlan = {
+  plan: CraftPlan;
+  operations: string[];
+};
 
 export type Operation = {
   id: string;
   priority: OperationPriority;
   status: OperationStatus;
   kind: OperationKind;
+  predicted_completion_at: string | null;
+  depends_on: string[];
 };
@@ -12,25 +12,45 @@ import { Loc, vec2ContainedBy } from './types';
 
 const matchesComplex = (loc: Loc, complex: StorageComplex) => {
   if ('FlatFloor' in complex) {
+    const { y_level, layers, stride } = complex.FlatFloor;
+    const onLayer = [...Array(layers).keys()].some(
+      (layer) => loc.vec3.y === y_level + layer * stride,
+    );
+
     return (
       loc.dim === complex.FlatFloor.dimension &&
-      loc.vec3.y === complex.FlatFloor.y_level &&
+      onLayer &&
       vec2ContainedBy(complex.FlatFloor.bounds, loc.vec3)
     );
   } else if ('Tower' in complex) {
-    const origin = complex.Tower.origin;
+    const { origin, radius, skipped_layers } = complex.Tower;
     return (
       loc.dim === complex.Tower.dimension &&
       loc.vec3.y >= origin.y &&
       loc.vec3.y < origin.y + complex.Tower.height &&
+      !skipped_layers.includes(loc.vec3.y - origin.y) &&
       vec2ContainedBy(
         [
-          { x: origin.x - 4, z: origin.z - 4 },
-          { x: origin.x + 4, z: origin.z + 4 },
+          { x: origin.x - radius, z: origin.z - radius },
+          { x: origin.x + radius, z: origin.z + radius },
         ],
         loc.vec3,
       )
     );
+  } else if ('Wall' in complex) {
+    const { origin, width, height, facing } = complex.Wall;
+    // The wall runs along X when facing north / south, along Z otherwise
+    const alongX = facing === 'North' || facing === 'South';
+    const offset = alongX ? loc.vec3.x - origin.x : loc.vec3.z - origin.z;
+
+    return (
+      loc.dim === complex.Wall.dimension &&
+      (alongX ? loc.vec3.z === origin.z : loc.vec3.x === origin.x) &&
+      offset >= 0 &&
+      offset < width &&
+      loc.vec3.y >= origin.y &&
+      loc.vec3.y < origin.y + height
+    );
   } else {
     return false;
   }
@@ -1,6 +1,17 @@
 import assert from 'assert';
-import { releaseHolds, ExtendedItem, executeOperation } from '../helpers';
-import { createHold, getSignConfig } from './automation';
+import {
+  releaseHolds,
+  ExtendedItem,
+  executeOperation,
+  pollOperation,
+} from '../helpers';
+import {
+  createHold,
+  decompressItem,
+  getNearestNode,
+  getOperation,
+  getSignConfig,
+} from './automation';
 import { HoldRequestFilter } from './automation_types';
 
 export type DeliveryItems = {
@@ -8,6 +19,37 @@ export type DeliveryItems = {
   count: number;
 }[];
 
+// Destinations starting with # are tags, delivered to the closest tagged drop-off
+export const TAG_PREFIX = '#';
+
+// Crafts blocks back into any items there are too few of loose, so they can be held
+const decompressItems = async (itemList: DeliveryItems): Promise<void> => {
+  for (const { item, count } of itemList) {
+    if (item.nbt !== null) continue;
+
+    // Items without a block form are left to the hold request to report
+    const queued = await decompressItem(item.item_id, count).catch(() => null);
+    if (!queued) continue;
+
+    for (const operation_id of queued.data.operations) {
+      await pollOperation(operation_id);
+
+      const {
+        data: { operation },
+      } = await getOperation(operation_id);
+
+      if (operation.kind.type === 'Craft') {
+        await releaseHolds([
+          ...operation.kind.recipe_source_holds.flatMap((hold) =>
+            hold === null ? [] : [hold],
+          ),
+          ...operation.kind.destination_holds,
+        ]).catch(() => null);
+      }
+    }
+  }
+};
+
 export const deliverItems = async (
   destinationLoc: string,
   itemList: DeliveryItems,
@@ -15,14 +57,20 @@ export const deliverItems = async (
   const {
     data: { nodes },
   } = await getSignConfig();
-  const destNode = nodes[destinationLoc];
+  const destTag = destinationLoc.startsWith(TAG_PREFIX)
+    ? destinationLoc.slice(TAG_PREFIX.length)
+    : null;
+  let destNode = destTag === null ? nodes[destinationLoc] : undefined;
 
-  assert(destNode, 'Destination location does not exist');
-  assert(destNode.dropoff, 'Destination does not have a drop-off location');
+  if (destTag === null) {
+    assert(destNode, 'Destination location does not exist');
+  }
 
   const holdsToDeliver: string[] = [];
 
   try {
+    await decompressItems(itemList);
+
     const itemRequests: HoldRequestFilter[] = itemList.map(
       ({ item, count }) => ({
         ItemMatch: {
@@ -46,6 +94,19 @@ export const deliverItems = async (
     // TODO Chunk deliveries
     assert(holdsToDeliver.length <= 27, 'Too many slots to deliver!');
 
+    if (destTag !== null) {
+      const { data: nearest } = await getNearestNode({
+        tag: destTag,
+        role: 'drop_off',
+        from_hold: holdsToDeliver[0],
+      });
+
+      destNode = nearest;
+    }
+
+    assert(destNode, 'No drop-off location found');
+    assert(destNode.dropoff, 'Destination does not have a drop-off location');
+
     await executeOperation(
       {
         type: 'DropItems',
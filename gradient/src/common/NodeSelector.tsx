@@ -1,6 +1,7 @@
 import { useEffect, useRef, useState } from 'react';
 import { useQuery } from 'react-query';
 import { getSignConfig } from '../api/automation';
+import { TAG_PREFIX } from '../api/delivery';
 import styled, { css } from 'styled-components';
 import { Fzf } from 'fzf';
 
@@ -70,6 +71,17 @@ export const NodeSelector = ({ submit: rawSubmit, purpose }: Props) => {
     })
     .map((node) => node.name);
 
+  // Deliveries can also go to the closest drop-off with a given tag
+  if (purpose === 'delivery') {
+    const tags = dataNodes
+      .filter((node) => node.dropoff)
+      .flatMap((node) => node.tags);
+
+    const tagNames = Array.from(new Set(tags)).map((tag) => TAG_PREFIX + tag);
+
+    validNodes.push(...tagNames);
+  }
+
   const fzf = new Fzf(validNodes, {
     tiebreakers: [(a, b) => nodeSort(a.item, b.item)],
   });
@@ -95,37 +95,15 @@ export const Config = () => {
       </List>
       <h1>Sign Parsing and Validation Errors</h1>
       <ul>
-        {data.data.validation_errors.map((err, idx) => {
-          if (err.type === 'DuplicatePathfindingNode') {
-            return (
-              <li key={idx}>
-                Attempt to create duplicate node with name {err.name}
-              </li>
-            );
-          } else if (err.type === 'UnknownNode') {
-            return (
-              <li key={idx}>
-                Node {err.name} was referenced, but it is unknown.
-              </li>
-            );
-          } else {
-            return <li key={idx}>Unknown error type</li>;
-          }
-        })}
-
-        {data.data.sign_parse_errors.map((err, idx) => {
-          if (err.type === 'NoMarker') {
-            return null;
-          } else if (err.type === 'NameEmpty') {
-            return <li key={idx}>Expected name, but no name was found</li>;
-          } else if (err.type === 'OffsetParseFailed') {
-            return <li key={idx}>Failed to parse location offset</li>;
-          } else if (err.type === 'UnknownSignType') {
-            return <li key={idx}>Unknown sign type specified</li>;
-          } else {
-            return <li key={idx}>Unknown error type</li>;
-          }
-        })}
+        {[...data.data.validation_errors, ...data.data.sign_parse_errors]
+          .filter((err) => err.type !== 'NoMarker')
+          .map((err, idx) => (
+            <li key={idx}>
+              {`${err.message} at (${err.location.vec3.x}, ${err.location.vec3.y}, ${err.location.vec3.z}) in ${err.location.dim}, `}
+              {`line ${err.line}: "${err.lines[err.line - 1]}"`}
+              {err.suggestion && ` (did you mean "${err.suggestion}"?)`}
+            </li>
+          ))}
       </ul>
     </Container>
   );
@@ -37,6 +37,7 @@ export const Stats = () => {
       <Row>
         <StatCard title="Agents Connected" value={stats.agents_connected} />
         <StatCard title="Slot Holds" value={stats.current_holds} />
+        <StatCard title="Cached Paths" value={stats.path_cache.cached_paths} />
       </Row>
       <Row>
         <code>
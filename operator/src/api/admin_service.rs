@@ -1,6 +1,19 @@
-use actix_web::{get, web, HttpResponse, Responder};
+use actix_web::{get, post, web, HttpResponse, Responder};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use crate::{state::StateData, stats::calculate_stats};
+use crate::{
+    config::Config,
+    graph_export::{render_dot, render_geojson},
+    sign_config_file::{DeclaredSignConfig, SignConfigFormat},
+    state::{
+        discrepancies::Discrepancy, path_penalties::EdgePenalty,
+        sign_config_history::SignConfigChange, travel_times::RollingEstimate, StateData,
+    },
+    stats::{calculate_capacity, calculate_stats},
+    types::Dimension,
+};
 
 #[get("/stats")]
 async fn stats(state: StateData) -> impl Responder {
@@ -11,6 +24,216 @@ async fn stats(state: StateData) -> impl Responder {
     HttpResponse::Ok().json(stats)
 }
 
+#[get("/capacity")]
+async fn capacity(state: StateData, config: web::Data<Config>) -> impl Responder {
+    let state = state.lock().unwrap();
+
+    let capacity = calculate_capacity(&state, &config);
+
+    HttpResponse::Ok().json(capacity)
+}
+
+#[derive(Serialize)]
+struct DiscrepancyList<'a> {
+    discrepancies: Vec<&'a Discrepancy>,
+}
+
+#[get("/discrepancies")]
+async fn discrepancies(state: StateData) -> impl Responder {
+    let state = state.lock().unwrap();
+
+    let discrepancies = state.discrepancies.iter().collect();
+
+    HttpResponse::Ok().json(DiscrepancyList { discrepancies })
+}
+
+#[derive(Serialize)]
+struct PenalizedEdge<'a> {
+    #[serde(flatten)]
+    penalty: &'a EdgePenalty,
+    current_cost: u32,
+}
+
+#[derive(Serialize)]
+struct PenalizedEdgeList<'a> {
+    edges: Vec<PenalizedEdge<'a>>,
+}
+
+#[get("/path_penalties")]
+async fn path_penalties(state: StateData) -> impl Responder {
+    let state = state.lock().unwrap();
+    let now = Utc::now();
+
+    let edges = state
+        .path_penalties
+        .iter()
+        .map(|penalty| PenalizedEdge {
+            penalty,
+            current_cost: penalty.cost(now),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(PenalizedEdgeList { edges })
+}
+
+#[derive(Serialize)]
+struct LearnedEdgeTime<'a> {
+    source_node: &'a str,
+    destination_node: &'a str,
+    seconds: &'a RollingEstimate,
+}
+
+#[derive(Serialize)]
+struct TravelTimesResponse<'a> {
+    blocks_per_second: Option<f64>,
+    edges: Vec<LearnedEdgeTime<'a>>,
+    operations: HashMap<&'static str, &'a RollingEstimate>,
+}
+
+#[get("/travel_times")]
+async fn travel_times(state: StateData) -> impl Responder {
+    let state = state.lock().unwrap();
+
+    let edges = state
+        .travel_times
+        .iter_edges()
+        .map(
+            |((source_node, destination_node), seconds)| LearnedEdgeTime {
+                source_node,
+                destination_node,
+                seconds,
+            },
+        )
+        .collect();
+
+    let operations = state
+        .travel_times
+        .iter_operations()
+        .map(|(kind_name, seconds)| (*kind_name, seconds))
+        .collect();
+
+    HttpResponse::Ok().json(TravelTimesResponse {
+        blocks_per_second: state.travel_times.blocks_per_second(),
+        edges,
+        operations,
+    })
+}
+
+#[derive(Deserialize)]
+struct SignConfigFormatQuery {
+    #[serde(default)]
+    format: SignConfigFormat,
+}
+
+#[get("/sign_config/export")]
+async fn export_sign_config(
+    state: StateData,
+    query: web::Query<SignConfigFormatQuery>,
+) -> impl Responder {
+    let signs = state.lock().unwrap().sign_config.export_signs();
+
+    match (DeclaredSignConfig { signs }).render(query.format) {
+        Ok(rendered) => HttpResponse::Ok()
+            .content_type(query.format.content_type())
+            .body(rendered),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[derive(Serialize)]
+struct ImportSignConfigResponse {
+    declared_signs: usize,
+}
+
+#[post("/sign_config/import")]
+async fn import_sign_config(
+    state: StateData,
+    query: web::Query<SignConfigFormatQuery>,
+    body: String,
+) -> impl Responder {
+    let declared = match DeclaredSignConfig::parse(&body, query.format) {
+        Ok(declared) => declared,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+
+    let declared_signs = declared.signs.len();
+    state
+        .lock()
+        .unwrap()
+        .sign_config
+        .set_declared_signs(declared.signs);
+
+    HttpResponse::Ok().json(ImportSignConfigResponse { declared_signs })
+}
+
+#[get("/sign_config/diff")]
+async fn sign_config_diff(state: StateData) -> impl Responder {
+    let state = state.lock().unwrap();
+
+    HttpResponse::Ok().json(state.sign_config.diff())
+}
+
+#[derive(Serialize)]
+struct SignConfigHistoryList<'a> {
+    generation: u64,
+    changes: Vec<&'a SignConfigChange>,
+}
+
+#[get("/sign_config/history")]
+async fn sign_config_history(state: StateData) -> impl Responder {
+    let state = state.lock().unwrap();
+    let generation = state.sign_config.get_config().generation;
+    let history = state.sign_config.history();
+
+    HttpResponse::Ok().json(SignConfigHistoryList {
+        generation,
+        changes: history.iter().collect(),
+    })
+}
+
+#[get("/sign_config/history/{generation}")]
+async fn sign_config_history_entry(state: StateData, generation: web::Path<u64>) -> impl Responder {
+    let state = state.lock().unwrap();
+    let history = state.sign_config.history();
+
+    match history.get(generation.into_inner()) {
+        Some(change) => HttpResponse::Ok().json(change),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[get("/graph/dot")]
+async fn graph_dot(state: StateData) -> impl Responder {
+    let sign_config = state.lock().unwrap().sign_config.get_config();
+
+    HttpResponse::Ok()
+        .content_type("text/vnd.graphviz")
+        .body(render_dot(&sign_config))
+}
+
+#[get("/graph/geojson/{dimension}")]
+async fn graph_geojson(state: StateData, dimension: web::Path<Dimension>) -> impl Responder {
+    let sign_config = state.lock().unwrap().sign_config.get_config();
+
+    HttpResponse::Ok()
+        .content_type("application/geo+json")
+        .json(render_geojson(&sign_config, dimension.into_inner()))
+}
+
 pub fn configure(app: &mut web::ServiceConfig) {
-    app.service(web::scope("/admin").service(stats));
+    app.service(
+        web::scope("/admin")
+            .service(stats)
+            .service(capacity)
+            .service(discrepancies)
+            .service(path_penalties)
+            .service(travel_times)
+            .service(export_sign_config)
+            .service(import_sign_config)
+            .service(sign_config_diff)
+            .service(sign_config_history)
+            .service(sign_config_history_entry)
+            .service(graph_dot)
+            .service(graph_geojson),
+    );
 }
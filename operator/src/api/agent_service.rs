@@ -2,20 +2,24 @@ use std::ops::DerefMut;
 
 use actix_web::{get, post, web, HttpResponse, Responder};
 use chrono::Utc;
+use hashbrown::HashSet;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    pathfinding::{PathfindingError, PfResultNode},
+    config::Config,
+    pathfinding::{FoundPath, PathEdge, PathfindingError, PfResultNode, TravelEstimator},
+    prediction::{apply_completed_operation, observed_during},
     state::{
         agents::Agent,
         alerts::{Alert, AlertSource},
+        discrepancies::{describe_changes, find_unexplained_changes},
         holds::Hold,
         operations::{Operation, OperationError, OperationStatus},
         sign_config::Sign,
         StateData,
     },
-    types::{Dimension, Inventory, Location, UnhashedItem, Vec2, Vec3},
+    types::{ContainerKind, Dimension, Inventory, Location, UnhashedItem, Vec2, Vec3},
 };
 
 #[derive(Serialize)]
@@ -84,16 +88,26 @@ async fn poll_operation(
     agent: Agent,
     state: StateData,
     poll_req: web::Json<PollOperationRequest>,
+    config: web::Data<Config>,
 ) -> impl Responder {
     let mut state = state.lock().unwrap();
+    let state = state.deref_mut();
 
     if agent.current_operation.is_some() {
         return HttpResponse::Conflict().body("Agent already is executing an operation");
     }
 
+    let estimator = TravelEstimator::new(&state.sign_config.get_config());
+
     let next_operation = state
         .operations
-        .take_next_operation(poll_req.location, poll_req.has_clear_inventory)
+        .take_next_operation(
+            poll_req.location,
+            poll_req.has_clear_inventory,
+            &estimator,
+            &state.travel_times,
+            config.learned_travel_times,
+        )
         .map(|op| op.clone());
 
     HttpResponse::Ok().json(match next_operation {
@@ -192,6 +206,26 @@ async fn operation_complete(
         Ok(op) => {
             let op = op.clone();
 
+            let mut predicted_slots = HashSet::new();
+
+            if operation_data.final_status == OperationStatus::Complete {
+                predicted_slots = apply_completed_operation(&mut state, &op);
+                state.travel_times.record_operation(&op);
+            }
+
+            // Slots whose contents after the operation are unknown, neither reported by the agent
+            // nor predicted, can't be checked until they are scanned again
+            for hold_id in op.holds() {
+                if let Some(hold) = state.holds.get(hold_id) {
+                    let (location, slot) = (hold.location, hold.slot);
+                    if !observed_during(&state, &location, &op)
+                        && !predicted_slots.contains(&(location, slot))
+                    {
+                        state.inventories.mark_touched(location, slot);
+                    }
+                }
+            }
+
             if operation_data.final_status == OperationStatus::Aborted {
                 state.alerts.add_alert(
                     AlertSource::Agent(agent.id),
@@ -210,33 +244,79 @@ pub struct InventoryScannedRequest {
     location: Location,
     slots: Vec<Option<UnhashedItem>>,
     open_from: Vec3,
+    kind: Option<ContainerKind>,
 }
 
 #[post("/inventory_scanned")]
 async fn inventory_scanned(
-    _agent: Agent,
+    agent: Agent,
     state: StateData,
     inventory_data: web::Json<InventoryScannedRequest>,
 ) -> impl Responder {
-    let mut state = state.lock().unwrap();
+    let mut state_lock = state.lock().unwrap();
+    let state = state_lock.deref_mut();
+    let reported_location = inventory_data.location;
     let open_from = inventory_data.open_from;
+    let kind = inventory_data
+        .kind
+        .unwrap_or_else(|| ContainerKind::from_slot_count(inventory_data.slots.len()));
+    let location = kind.canonical_location(reported_location);
+
+    let inventory = Inventory {
+        kind,
+        slots: inventory_data
+            .into_inner()
+            .slots
+            .into_iter()
+            .map(|slot| match slot {
+                None => None,
+                Some(unhashed_item) => Some(unhashed_item.into_item()),
+            })
+            .collect(),
+        scanned_at: Utc::now(),
+        open_from,
+        predicted: false,
+    };
+
+    // Slots being worked on right now are expected to change, as are slots changed by operations
+    // in a way that wasn't recorded
+    let mut explained_slots = state.inventories.take_touched(&location);
+    for hold_id in state
+        .operations
+        .iter(OperationStatus::InProgress)
+        .flat_map(|op| op.holds())
+    {
+        if let Some(hold) = state.holds.get(hold_id) {
+            if hold.location == location {
+                explained_slots.insert(hold.slot);
+            }
+        }
+    }
 
-    state.inventories.set_inventory_at(
-        inventory_data.location,
-        Inventory {
-            slots: inventory_data
-                .into_inner()
-                .slots
-                .into_iter()
-                .map(|slot| match slot {
-                    None => None,
-                    Some(unhashed_item) => Some(unhashed_item.into_item()),
-                })
-                .collect(),
-            scanned_at: Utc::now(),
-            open_from,
-        },
-    );
+    let unexplained_changes = state
+        .inventories
+        .inventory_contents_at(&location)
+        .map(|previous| find_unexplained_changes(location, previous, &inventory, &explained_slots))
+        .unwrap_or_default();
+
+    state
+        .inventories
+        .set_inventory_at(reported_location, inventory);
+
+    if let Some(operation_id) = agent.current_operation {
+        state.operations.record_report(operation_id, location);
+    }
+
+    if !unexplained_changes.is_empty() {
+        state.alerts.add_alert(
+            AlertSource::Operator,
+            describe_changes(location, &unexplained_changes),
+        );
+    }
+
+    for discrepancy in unexplained_changes {
+        state.discrepancies.record(discrepancy);
+    }
 
     HttpResponse::Ok()
 }
@@ -250,7 +330,10 @@ pub struct PathfindingRequest {
 #[derive(Serialize)]
 #[serde(tag = "type")]
 enum PathfindingResponse {
-    PathFound { path: Vec<PfResultNode> },
+    PathFound {
+        path: Vec<PfResultNode>,
+        edges: Vec<Vec<PathEdge>>,
+    },
     Error(PathfindingError),
 }
 
@@ -260,16 +343,108 @@ async fn pathfinding(
     req: web::Json<PathfindingRequest>,
     state: StateData,
 ) -> impl Responder {
-    let state = state.lock().unwrap();
+    // Paths are searched outside of the state lock
+    let graph = state.lock().unwrap().sign_config.get_path_graph();
 
-    let path = crate::pathfinding::find_path(req.start_loc, req.end_loc, &state);
+    let path = crate::pathfinding::find_path(req.start_loc, req.end_loc, &graph);
 
     match path {
-        Ok(path) => HttpResponse::Ok().json(PathfindingResponse::PathFound { path }),
+        Ok(FoundPath { path, edges }) => {
+            HttpResponse::Ok().json(PathfindingResponse::PathFound { path, edges })
+        }
         Err(err) => HttpResponse::InternalServerError().json(PathfindingResponse::Error(err)),
     }
 }
 
+#[derive(Deserialize)]
+pub struct PathFailedRequest {
+    // Edges of the leg the agent couldn't complete, as given with the path
+    edges: Vec<PathEdge>,
+}
+
+#[post("/path_failed")]
+async fn path_failed(
+    agent: Agent,
+    req: web::Json<PathFailedRequest>,
+    state: StateData,
+) -> impl Responder {
+    let mut state = state.lock().unwrap();
+
+    if req.edges.is_empty() {
+        return HttpResponse::BadRequest().body("No edges given for failed segment");
+    }
+
+    for edge in req.edges.iter() {
+        let failures = state
+            .path_penalties
+            .record_failure(&edge.source_node, &edge.destination_node)
+            .failures;
+
+        state.alerts.add_alert(
+            AlertSource::Agent(agent.id),
+            format!(
+                "Unable to travel from {} to {}, penalizing edge ({} recent failures)",
+                edge.source_node, edge.destination_node, failures
+            ),
+        );
+    }
+
+    // Apply the penalty straight away so the next path request avoids the edge
+    let edge_penalties = state.path_penalties.current_costs();
+    state.sign_config.set_edge_penalties(edge_penalties);
+
+    HttpResponse::Ok().body("success")
+}
+
+#[derive(Deserialize)]
+pub struct SegmentTraveledRequest {
+    // Edges of the leg traveled, as given with the path
+    edges: Vec<PathEdge>,
+    seconds: f64,
+}
+
+#[post("/segment_traveled")]
+async fn segment_traveled(
+    _agent: Agent,
+    req: web::Json<SegmentTraveledRequest>,
+    state: StateData,
+) -> impl Responder {
+    let mut state = state.lock().unwrap();
+    let sign_config = state.sign_config.get_config();
+
+    // Walking distance of each edge, portal edges having none
+    let distances = req
+        .edges
+        .iter()
+        .map(|edge| {
+            let source = sign_config.nodes.get(&edge.source_node)?.location;
+            let destination = sign_config.nodes.get(&edge.destination_node)?.location;
+
+            (source.dim == destination.dim).then(|| source.vec3.dist(destination.vec3))
+        })
+        .collect::<Vec<_>>();
+    let total_distance = distances.iter().flatten().sum::<f64>();
+
+    // Legs over several edges in a straight line have their time split by distance
+    for (edge, distance) in req.edges.iter().zip(distances) {
+        let seconds = match distance {
+            Some(distance) if req.edges.len() > 1 && total_distance > 0.0 => {
+                req.seconds * distance / total_distance
+            }
+            _ => req.seconds,
+        };
+
+        state.travel_times.record_segment(
+            &edge.source_node,
+            &edge.destination_node,
+            distance,
+            seconds,
+        );
+    }
+
+    HttpResponse::Ok().body("success")
+}
+
 #[derive(Deserialize)]
 pub struct ScanRegion {
     signs: Vec<Sign>,
@@ -317,6 +492,8 @@ pub fn configure(app: &mut web::ServiceConfig) {
             .service(operation_complete)
             .service(inventory_scanned)
             .service(pathfinding)
+            .service(path_failed)
+            .service(segment_traveled)
             .service(sign_scan_data),
     );
 }
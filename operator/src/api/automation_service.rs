@@ -1,12 +1,21 @@
 use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
+    compression::queue_decompression,
+    crafting::{item_id_by_name, queue_crafts},
+    crafting_planner::{plan_crafts, queue_plan},
+    pathfinding::{find_aligned_node, PathGraph},
+    smelting::{predicted_completion_at, predicted_ready_at, queue_smelting},
     state::{
         holds::Hold,
         inventories::{InventoryListingOptions, ShulkerUnpacking},
         operations::{Operation, OperationKind, OperationPriority},
+        sign_config::PathfindingNode,
+        smelting::{FurnaceLoad, SmeltJob},
         StateData,
     },
     types::{HoldMatchError, HoldRequestFilter, Item, Location, Vec3},
@@ -17,6 +26,7 @@ struct InventoryWithLoc {
     pub slots: Vec<Option<Item>>,
     pub loc: Location,
     pub open_from: Vec3,
+    pub predicted: bool,
 }
 
 #[get("/inventory_contents")]
@@ -30,6 +40,7 @@ async fn inventory_contents(state: StateData) -> impl Responder {
             slots: inv.slots.clone(),
             loc: *loc,
             open_from: inv.open_from,
+            predicted: inv.predicted,
         })
         .collect();
 
@@ -53,6 +64,155 @@ async fn sign_config(state: StateData) -> impl Responder {
     HttpResponse::Ok().json(sign_config.as_ref())
 }
 
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum NodeRole {
+    Pickup,
+    DropOff,
+    ShulkerStation,
+}
+
+impl NodeRole {
+    fn matches(&self, node: &PathfindingNode) -> bool {
+        match self {
+            NodeRole::Pickup => node.pickup.is_some(),
+            NodeRole::DropOff => node.dropoff.is_some(),
+            NodeRole::ShulkerStation => node.shulker_station,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NodeQuery {
+    tag: Option<String>,
+    role: Option<NodeRole>,
+    // Costs are measured from a node by name, or from the node a held slot is reached from
+    from: Option<String>,
+    from_hold: Option<Uuid>,
+}
+
+#[derive(Serialize)]
+struct NodeMatch<'a> {
+    #[serde(flatten)]
+    node: &'a PathfindingNode,
+    cost: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct NodeList<'a> {
+    nodes: Vec<NodeMatch<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum NodeQueryError {
+    UnknownStartingNode,
+    UnknownHold,
+    NoStartingPoint,
+    NoMatchingNode,
+}
+
+// The path graph, and the node costs are measured from if the query gives one
+fn resolve_starting_node(
+    state: &StateData,
+    query: &NodeQuery,
+) -> Result<(Arc<PathGraph>, Option<String>), NodeQueryError> {
+    // Paths are searched outside of the state lock
+    let (graph, from_location) = {
+        let state = state.lock().unwrap();
+        let from_location = match query.from_hold {
+            Some(hold_id) => Some(
+                state
+                    .holds
+                    .get(hold_id)
+                    .ok_or(NodeQueryError::UnknownHold)?
+                    .location,
+            ),
+            None => None,
+        };
+
+        (state.sign_config.get_path_graph(), from_location)
+    };
+
+    let starting_node = match (&query.from, from_location) {
+        (Some(name), _) => {
+            if !graph.sign_config().nodes.contains_key(name) {
+                return Err(NodeQueryError::UnknownStartingNode);
+            }
+
+            Some(name.clone())
+        }
+        (None, Some(location)) => Some(
+            find_aligned_node(location, graph.sign_config())
+                .ok_or(NodeQueryError::UnknownStartingNode)?,
+        ),
+        (None, None) => None,
+    };
+
+    Ok((graph, starting_node))
+}
+
+// Matching nodes, cheapest to reach first, with unreachable nodes last
+fn matching_nodes<'a>(
+    graph: &'a PathGraph,
+    starting_node: Option<&str>,
+    query: &NodeQuery,
+) -> Vec<NodeMatch<'a>> {
+    let tag = query.tag.as_ref().map(|tag| tag.to_lowercase());
+
+    let mut matches = graph
+        .sign_config()
+        .nodes
+        .values()
+        .filter(|node| tag.as_ref().is_none_or(|tag| node.tags.contains(tag)))
+        .filter(|node| query.role.is_none_or(|role| role.matches(node)))
+        .map(|node| NodeMatch {
+            node,
+            cost: starting_node
+                .and_then(|starting_node| graph.node_cost(starting_node, &node.name)),
+        })
+        .collect::<Vec<_>>();
+
+    matches.sort_by(|a, b| {
+        a.cost
+            .is_none()
+            .cmp(&b.cost.is_none())
+            .then(a.cost.cmp(&b.cost))
+            .then(a.node.name.cmp(&b.node.name))
+    });
+
+    matches
+}
+
+#[get("/nodes")]
+async fn nodes_index(state: StateData, query: web::Query<NodeQuery>) -> impl Responder {
+    match resolve_starting_node(&state, &query) {
+        Ok((graph, starting_node)) => HttpResponse::Ok().json(NodeList {
+            nodes: matching_nodes(&graph, starting_node.as_deref(), &query),
+        }),
+        Err(err) => HttpResponse::BadRequest().json(err),
+    }
+}
+
+#[get("/nodes/nearest")]
+async fn nearest_node(state: StateData, query: web::Query<NodeQuery>) -> impl Responder {
+    let (graph, starting_node) = match resolve_starting_node(&state, &query) {
+        Ok((graph, Some(starting_node))) => (graph, starting_node),
+        Ok((_graph, None)) => {
+            return HttpResponse::BadRequest().json(NodeQueryError::NoStartingPoint)
+        }
+        Err(err) => return HttpResponse::BadRequest().json(err),
+    };
+
+    match matching_nodes(&graph, Some(&starting_node), &query)
+        .into_iter()
+        .find(|node_match| node_match.cost.is_some())
+    {
+        Some(node_match) => HttpResponse::Ok().json(node_match),
+        None => HttpResponse::NotFound().json(NodeQueryError::NoMatchingNode),
+    }
+}
+
 #[derive(Serialize)]
 struct HoldList<'a> {
     holds: &'a Vec<&'a Hold>,
@@ -182,17 +342,183 @@ async fn get_operation(state: StateData, op_id: web::Path<Uuid>) -> impl Respond
     }
 }
 
+#[derive(Deserialize)]
+struct CraftRequest {
+    item: String,
+    count: u32,
+    priority: Option<OperationPriority>,
+}
+
+#[post("/craft")]
+async fn craft(state: StateData, req: web::Json<CraftRequest>) -> impl Responder {
+    let mut state = state.lock().unwrap();
+
+    let queued = item_id_by_name(&req.item).and_then(|item_id| {
+        queue_crafts(
+            &mut state,
+            item_id,
+            req.count,
+            req.priority.unwrap_or(OperationPriority::UserInteractive),
+        )
+    });
+
+    match queued {
+        Ok(queued) => HttpResponse::Ok().json(queued),
+        Err(error) => HttpResponse::BadRequest().json(error),
+    }
+}
+
+#[derive(Deserialize)]
+struct CraftPlanRequest {
+    item: String,
+    count: u32,
+    // Queues the plan's Craft operations rather than only returning the plan
+    execute: Option<bool>,
+    priority: Option<OperationPriority>,
+}
+
+#[post("/craft/plan")]
+async fn craft_plan(state: StateData, req: web::Json<CraftPlanRequest>) -> impl Responder {
+    let mut state = state.lock().unwrap();
+
+    let item_id = match item_id_by_name(&req.item) {
+        Ok(item_id) => item_id,
+        Err(error) => return HttpResponse::BadRequest().json(error),
+    };
+
+    if !req.execute.unwrap_or(false) {
+        return HttpResponse::Ok().json(plan_crafts(&state, item_id, req.count));
+    }
+
+    match queue_plan(
+        &mut state,
+        item_id,
+        req.count,
+        req.priority.unwrap_or(OperationPriority::UserInteractive),
+    ) {
+        Ok(queued) => HttpResponse::Ok().json(queued),
+        Err(error) => HttpResponse::BadRequest().json(error),
+    }
+}
+
+#[derive(Deserialize)]
+struct DecompressRequest {
+    item_id: u32,
+    // Loose items storage should hold once the queued operations complete
+    count: u32,
+    priority: Option<OperationPriority>,
+}
+
+#[post("/decompress")]
+async fn decompress(state: StateData, req: web::Json<DecompressRequest>) -> impl Responder {
+    let mut state = state.lock().unwrap();
+
+    match queue_decompression(
+        &mut state,
+        req.item_id,
+        req.count,
+        req.priority.unwrap_or(OperationPriority::UserInteractive),
+    ) {
+        Ok(queued) => HttpResponse::Ok().json(queued),
+        Err(error) => HttpResponse::BadRequest().json(error),
+    }
+}
+
+#[derive(Deserialize)]
+struct SmeltRequest {
+    // The item to put in the furnaces, such as raw_iron
+    item: String,
+    count: u32,
+    priority: Option<OperationPriority>,
+}
+
+#[post("/smelt")]
+async fn smelt(state: StateData, req: web::Json<SmeltRequest>) -> impl Responder {
+    let mut state = state.lock().unwrap();
+
+    let item_id = match item_id_by_name(&req.item) {
+        Ok(item_id) => item_id,
+        Err(error) => return HttpResponse::BadRequest().json(error),
+    };
+
+    match queue_smelting(
+        &mut state,
+        item_id,
+        req.count,
+        req.priority.unwrap_or(OperationPriority::UserInteractive),
+    ) {
+        Ok(job) => HttpResponse::Ok().json(SmeltJobStatus {
+            predicted_completion_at: predicted_completion_at(&state, &job),
+            job: &job,
+        }),
+        Err(error) => HttpResponse::BadRequest().json(error),
+    }
+}
+
+#[derive(Serialize)]
+struct SmeltJobStatus<'a> {
+    #[serde(flatten)]
+    job: &'a SmeltJob,
+    predicted_completion_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+struct FurnaceLoadStatus<'a> {
+    #[serde(flatten)]
+    load: &'a FurnaceLoad,
+    predicted_ready_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+struct SmeltingStatus<'a> {
+    jobs: Vec<SmeltJobStatus<'a>>,
+    loads: Vec<FurnaceLoadStatus<'a>>,
+}
+
+#[get("/smelting")]
+async fn smelting_status(state: StateData) -> impl Responder {
+    let state = state.lock().unwrap();
+
+    HttpResponse::Ok().json(SmeltingStatus {
+        jobs: state
+            .smelting
+            .jobs
+            .iter()
+            .map(|job| SmeltJobStatus {
+                job,
+                predicted_completion_at: predicted_completion_at(&state, job),
+            })
+            .collect(),
+        loads: state
+            .smelting
+            .loads
+            .iter()
+            .map(|load| FurnaceLoadStatus {
+                load,
+                predicted_ready_at: predicted_ready_at(&state, load),
+            })
+            .collect(),
+    })
+}
+
 pub fn configure(app: &mut web::ServiceConfig) {
     app.service(
         web::scope("/automation")
             .service(inventory_contents)
             .service(inventory_listing)
             .service(sign_config)
+            .service(nodes_index)
+            .service(nearest_node)
             .service(holds_index)
             .service(holds_create)
             .service(remove_hold)
             .service(renew_hold)
             .service(create_operation)
+            .service(craft)
+            .service(craft_plan)
+            .service(decompress)
+            .service(smelt)
+            .service(smelting_status)
             .service(get_operation),
     );
 }
@@ -0,0 +1,65 @@
+use crate::{
+    crafting::{available_count, item_name, queue_recipe, CraftError, QueuedCrafts},
+    data::Compression,
+    state::{operations::OperationPriority, State},
+    types::MC_DATA,
+};
+
+fn compression_for_item(item_id: u32) -> Option<&'static Compression> {
+    MC_DATA
+        .compressions
+        .iter()
+        .find(|compression| compression.item_id == item_id)
+}
+
+// Queues as many block to item crafts as it takes for storage to hold count of the item loose,
+// queueing nothing if it already does
+pub fn queue_decompression(
+    state: &mut State,
+    item_id: u32,
+    count: u32,
+    priority: OperationPriority,
+) -> Result<QueuedCrafts, CraftError> {
+    let compression = compression_for_item(item_id).ok_or_else(|| CraftError::NoRecipe {
+        name: item_name(item_id),
+    })?;
+
+    let loose = available_count(state, item_id);
+    let crafts = count.saturating_sub(loose).div_ceil(9);
+
+    let mut operations = vec![];
+
+    if crafts == 0 {
+        return Ok(QueuedCrafts {
+            operations,
+            crafted: 0,
+        });
+    }
+
+    let blocks = available_count(state, compression.block_id);
+    if blocks < crafts {
+        return Err(CraftError::MissingIngredient {
+            name: item_name(compression.block_id),
+            needed: crafts,
+            available: blocks,
+        });
+    }
+
+    let crafts = match queue_recipe(
+        state,
+        &compression.decompress,
+        crafts,
+        priority,
+        &mut vec![],
+        &mut operations,
+    ) {
+        Ok(crafts) => crafts,
+        Err((0, error)) => return Err(error),
+        Err((crafts, _error)) => crafts,
+    };
+
+    Ok(QueuedCrafts {
+        operations,
+        crafted: crafts * 9,
+    })
+}
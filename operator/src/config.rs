@@ -12,6 +12,59 @@ pub struct Config {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+
+    // Containers that see no access are rescanned this often
+    #[serde(default = "default_rescan_interval_minutes")]
+    pub rescan_interval_minutes: i64,
+    // Frequently accessed containers are never rescanned more often than this
+    #[serde(default = "default_rescan_min_interval_minutes")]
+    pub rescan_min_interval_minutes: i64,
+    // Upper bound on background rescans queued at once
+    #[serde(default = "default_rescan_max_outstanding")]
+    pub rescan_max_outstanding: usize,
+
+    // Upper bound on shulker loads, and separately unloads, queued at once
+    #[serde(default = "default_shulker_max_outstanding")]
+    pub shulker_max_outstanding: usize,
+
+    // Schedule operations and weight path edges by learned travel times rather than distance
+    #[serde(default = "default_learned_travel_times")]
+    pub learned_travel_times: bool,
+
+    // Declared sign layout (JSON, or TOML by extension) loaded before agents have scanned
+    #[serde(default)]
+    pub sign_config_file: Option<String>,
+
+    // Items kept in stock by crafting more when they run low
+    #[serde(default)]
+    pub stock_targets: Vec<StockTarget>,
+    // An item is restocked at most this often, and missing ingredients are retried as often
+    #[serde(default = "default_stock_keeper_interval_minutes")]
+    pub stock_keeper_interval_minutes: i64,
+    // Upper bound on restocking Craft operations queued at once
+    #[serde(default = "default_stock_keeper_max_outstanding")]
+    pub stock_keeper_max_outstanding: usize,
+
+    // Loose items kept of each compressible item, with the surplus crafted into blocks. Items are
+    // not compressed when unset.
+    #[serde(default)]
+    pub compression_threshold: Option<u32>,
+    // Upper bound on compressing Craft operations queued at once
+    #[serde(default = "default_compression_max_outstanding")]
+    pub compression_max_outstanding: usize,
+
+    // Items furnaces are fuelled with, defaulting to every known fuel except blaze rods
+    #[serde(default)]
+    pub smelting_fuels: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StockTarget {
+    pub item: String,
+    pub target: u32,
+    // Restocking starts once there are fewer than this many, defaulting to the target
+    #[serde(default)]
+    pub threshold: Option<u32>,
 }
 
 fn default_host() -> String {
@@ -22,6 +75,38 @@ fn default_port() -> u16 {
     6322
 }
 
+fn default_rescan_interval_minutes() -> i64 {
+    120
+}
+
+fn default_rescan_min_interval_minutes() -> i64 {
+    10
+}
+
+fn default_rescan_max_outstanding() -> usize {
+    8
+}
+
+fn default_shulker_max_outstanding() -> usize {
+    2
+}
+
+fn default_learned_travel_times() -> bool {
+    true
+}
+
+fn default_stock_keeper_interval_minutes() -> i64 {
+    5
+}
+
+fn default_stock_keeper_max_outstanding() -> usize {
+    8
+}
+
+fn default_compression_max_outstanding() -> usize {
+    2
+}
+
 pub fn read_config() -> Result<Config, figment::Error> {
     Figment::new()
         .merge(Toml::file("operator.toml"))
@@ -0,0 +1,554 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    data::Recipe,
+    state::{
+        operations::{Operation, OperationKind, OperationPriority},
+        State,
+    },
+    types::{HoldRequestFilter, Location, Vec3, MC_DATA},
+};
+
+#[derive(Error, Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum CraftError {
+    #[error("Unknown item {name}")]
+    UnknownItem { name: String },
+    #[error("No crafting table recipe makes {name}")]
+    NoRecipe { name: String },
+    #[error("Not enough {name} in storage, {needed} needed but {available} available")]
+    MissingIngredient {
+        name: String,
+        needed: u32,
+        available: u32,
+    },
+    #[error("No crafting station has been set up")]
+    NoCraftingStation,
+    #[error("Not enough empty slots in storage for the crafted items")]
+    NoSpace,
+}
+
+#[derive(Serialize)]
+pub struct QueuedCrafts {
+    pub operations: Vec<Uuid>,
+    // Items that will have been crafted once every operation completes
+    pub crafted: u32,
+}
+
+pub fn item_name(item_id: u32) -> String {
+    MC_DATA
+        .items_by_id
+        .get(&item_id)
+        .map(|item| item.name.clone())
+        .unwrap_or_else(|| item_id.to_string())
+}
+
+pub fn stack_size(item_id: u32) -> u32 {
+    MC_DATA
+        .items_by_id
+        .get(&item_id)
+        .map_or(64, |item| item.stack_size)
+}
+
+// Accepts names with or without the minecraft: prefix
+pub fn item_id_by_name(name: &str) -> Result<u32, CraftError> {
+    MC_DATA
+        .items_by_name
+        .get(name.strip_prefix("minecraft:").unwrap_or(name))
+        .map(|item| item.id)
+        .ok_or_else(|| CraftError::UnknownItem {
+            name: name.to_owned(),
+        })
+}
+
+#[derive(Clone)]
+pub struct SourceSlot {
+    pub location: Location,
+    pub slot: u32,
+    pub open_from: Vec3,
+    pub count: u32,
+}
+
+// Unheld slots of the plain item, fullest first
+pub fn ingredient_slots(state: &State, item_id: u32) -> Vec<SourceSlot> {
+    let mut slots = state
+        .inventories
+        .iter_slots()
+        .filter_map(|(location, slot, item, open_from)| {
+            let item = item.as_ref()?;
+
+            if item.item_id != item_id
+                || !item.nbt.is_null()
+                || state.holds.existing_hold(location, slot as u32).is_some()
+            {
+                return None;
+            }
+
+            Some(SourceSlot {
+                location,
+                slot: slot as u32,
+                open_from,
+                count: item.count,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    slots.sort_by(|a, b| a.count.cmp(&b.count).reverse());
+
+    slots
+}
+
+pub fn available_count(state: &State, item_id: u32) -> u32 {
+    ingredient_slots(state, item_id)
+        .iter()
+        .map(|slot| slot.count)
+        .sum()
+}
+
+// Where one cell of a batch takes its ingredients from
+#[derive(Clone, Copy)]
+enum CellSource {
+    // Index into the supply's held sources
+    Source(usize),
+    // Index into the supply's unheld slots of the cell's ingredient
+    Slot(usize),
+}
+
+type BatchCells = [Option<CellSource>; 9];
+
+// What batches can take ingredients from, held sources and unheld slots in storage. Every cell of a
+// batch takes all of its items from a single source or slot, so ingredients split over small
+// stacks only make small batches.
+#[derive(Default)]
+pub struct Supply {
+    // Item and count left of each held source, in the order of the sources it was made from
+    sources: Vec<(u32, u32)>,
+    // Unheld slots of each item loaded so far, fullest first
+    slots: HashMap<u32, Vec<SourceSlot>>,
+}
+
+impl Supply {
+    pub fn new(sources: &[SourceHold]) -> Self {
+        Supply {
+            sources: sources
+                .iter()
+                .map(|source| (source.item_id, source.left))
+                .collect(),
+            slots: HashMap::new(),
+        }
+    }
+
+    // Slots are only loaded once, so slots used by earlier batches stay used
+    fn load_recipe(&mut self, state: &State, recipe: &Recipe) {
+        for ingredient in recipe.grid.iter().flatten() {
+            self.slots
+                .entry(*ingredient)
+                .or_insert_with(|| ingredient_slots(state, *ingredient));
+        }
+    }
+
+    pub fn available(&self, item_id: u32) -> u32 {
+        let in_sources = self
+            .sources
+            .iter()
+            .filter(|(source_item_id, _left)| *source_item_id == item_id)
+            .map(|(_item_id, left)| left)
+            .sum::<u32>();
+        let in_slots = self
+            .slots
+            .get(&item_id)
+            .map_or(0, |slots| slots.iter().map(|slot| slot.count).sum());
+
+        in_sources + in_slots
+    }
+
+    // Sources are used before storage, and a slot feeds every cell it still has enough left for.
+    // Errors with the ingredient no source or slot has enough of.
+    fn pick_cells(&self, recipe: &Recipe, crafts: u32) -> Result<BatchCells, u32> {
+        let mut sources_left = self
+            .sources
+            .iter()
+            .map(|(_item_id, left)| *left)
+            .collect::<Vec<_>>();
+        let mut slots_left = HashMap::<u32, Vec<u32>>::new();
+        let mut cells = [None; 9];
+
+        for (cell, ingredient) in recipe.grid.iter().enumerate() {
+            let ingredient = match ingredient {
+                Some(ingredient) => *ingredient,
+                None => continue,
+            };
+
+            let source = self
+                .sources
+                .iter()
+                .zip(sources_left.iter())
+                .position(|((item_id, _left), left)| *item_id == ingredient && *left >= crafts);
+
+            if let Some(idx) = source {
+                sources_left[idx] -= crafts;
+                cells[cell] = Some(CellSource::Source(idx));
+
+                continue;
+            }
+
+            let slots_left = slots_left.entry(ingredient).or_insert_with(|| {
+                self.slots
+                    .get(&ingredient)
+                    .map(|slots| slots.iter().map(|slot| slot.count).collect())
+                    .unwrap_or_default()
+            });
+
+            match slots_left.iter().position(|left| *left >= crafts) {
+                Some(idx) => {
+                    slots_left[idx] -= crafts;
+                    cells[cell] = Some(CellSource::Slot(idx));
+                }
+                None => return Err(ingredient),
+            }
+        }
+
+        Ok(cells)
+    }
+
+    // The largest batch of at most limit crafts there are ingredients for, or the ingredient there
+    // is not enough of for a single craft
+    fn largest_batch(&self, recipe: &Recipe, limit: u32) -> Result<(u32, BatchCells), u32> {
+        let mut shortage = None;
+
+        for crafts in (1..=limit).rev() {
+            match self.pick_cells(recipe, crafts) {
+                Ok(cells) => return Ok((crafts, cells)),
+                Err(ingredient) => shortage = Some(ingredient),
+            }
+        }
+
+        Err(shortage.unwrap())
+    }
+
+    // Slots are held by the batch's operation, so nothing left in them is available to others
+    fn use_batch(&mut self, recipe: &Recipe, crafts: u32, cells: &BatchCells) {
+        let mut used_slots = vec![];
+
+        for (cell, source) in cells.iter().enumerate() {
+            match (recipe.grid[cell], source) {
+                (_, Some(CellSource::Source(idx))) => self.sources[*idx].1 -= crafts,
+                (Some(ingredient), Some(CellSource::Slot(idx))) => {
+                    used_slots.push((ingredient, *idx))
+                }
+                _ => {}
+            }
+        }
+
+        used_slots.sort();
+        used_slots.dedup();
+
+        for (ingredient, idx) in used_slots.into_iter().rev() {
+            self.slots.get_mut(&ingredient).unwrap().remove(idx);
+        }
+
+        let result_stack_size = stack_size(recipe.result_id);
+        let mut results_left = recipe.result_count * crafts;
+
+        while results_left > 0 {
+            let left = results_left.min(result_stack_size);
+            results_left -= left;
+
+            self.sources.push((recipe.result_id, left));
+        }
+    }
+
+    // Takes the ingredients for crafts of the recipe in the batches queue_recipe would queue,
+    // giving the number of batches, or the crafts taken before running short and the ingredient
+    pub fn use_recipe(
+        &mut self,
+        state: &State,
+        recipe: &Recipe,
+        crafts: u32,
+    ) -> Result<u32, (u32, u32)> {
+        self.load_recipe(state, recipe);
+
+        let max_batch = max_batch(recipe);
+        let mut crafts_remaining = crafts;
+        let mut batches = 0;
+
+        while crafts_remaining > 0 {
+            let (batch, cells) = self
+                .largest_batch(recipe, crafts_remaining.min(max_batch))
+                .map_err(|ingredient| (crafts - crafts_remaining, ingredient))?;
+
+            self.use_batch(recipe, batch, &cells);
+            crafts_remaining -= batch;
+            batches += 1;
+        }
+
+        Ok(batches)
+    }
+
+    // Only errors once there is less of the ingredient than a single craft takes, so the count
+    // available is always short of the count needed
+    pub fn shortage(&self, recipe: &Recipe, ingredient: u32, crafts: u32) -> CraftError {
+        let per_craft = recipe.ingredient_counts()[&ingredient];
+
+        CraftError::MissingIngredient {
+            name: item_name(ingredient),
+            needed: per_craft * crafts,
+            available: self.available(ingredient),
+        }
+    }
+}
+
+// The first recipe for the item that storage holds enough ingredients to make count with
+fn choose_recipe(state: &State, item_id: u32, count: u32) -> Result<&'static Recipe, CraftError> {
+    let recipes = MC_DATA
+        .recipes_by_result
+        .get(&item_id)
+        .filter(|recipes| !recipes.is_empty())
+        .ok_or_else(|| CraftError::NoRecipe {
+            name: item_name(item_id),
+        })?;
+
+    let mut first_shortage = None;
+
+    for recipe in recipes.iter() {
+        let crafts = count.div_ceil(recipe.result_count);
+        let mut supply = Supply::default();
+
+        match supply.use_recipe(state, recipe, crafts) {
+            Ok(_batches) => return Ok(recipe),
+            Err((crafted, ingredient)) => {
+                first_shortage
+                    .get_or_insert_with(|| supply.shortage(recipe, ingredient, crafts - crafted));
+            }
+        }
+    }
+
+    Err(first_shortage.unwrap())
+}
+
+pub fn release_holds(state: &mut State, holds: &[Uuid]) {
+    for hold in holds {
+        state.holds.remove(*hold);
+    }
+}
+
+// A hold filled by an earlier Craft operation, which later crafts take ingredients from before
+// storage. Holds on slots in storage are private to the one operation they were made for.
+pub struct SourceHold {
+    hold: Uuid,
+    item_id: u32,
+    // Items left once the cells already using the hold are filled
+    left: u32,
+    filled_by: Uuid,
+}
+
+// Holds the ingredient slots and space for one batch of crafts and queues the operation for it,
+// adding the crafted items to the sources
+fn queue_batch(
+    state: &mut State,
+    recipe: &Recipe,
+    crafts: u32,
+    cells: &BatchCells,
+    supply: &Supply,
+    priority: OperationPriority,
+    sources: &mut Vec<SourceHold>,
+) -> Result<Uuid, CraftError> {
+    let mut created_holds = vec![];
+    let mut slot_holds = HashMap::new();
+    let mut depends_on = vec![];
+    let mut recipe_source_holds = vec![None; 9];
+
+    for (cell, source) in cells.iter().enumerate() {
+        let hold = match (recipe.grid[cell], source) {
+            (_, Some(CellSource::Source(idx))) => {
+                let source = &sources[*idx];
+
+                if !depends_on.contains(&source.filled_by) {
+                    depends_on.push(source.filled_by);
+                }
+
+                source.hold
+            }
+            (Some(ingredient), Some(CellSource::Slot(idx))) => {
+                *slot_holds.entry((ingredient, *idx)).or_insert_with(|| {
+                    let slot = &supply.slots[&ingredient][*idx];
+                    let hold = state
+                        .holds
+                        .create(slot.location, slot.slot, slot.open_from)
+                        .unwrap()
+                        .id;
+
+                    created_holds.push(hold);
+
+                    hold
+                })
+            }
+            _ => continue,
+        };
+
+        recipe_source_holds[cell] = Some(hold);
+    }
+
+    let involved_locations = recipe_source_holds
+        .iter()
+        .flatten()
+        .map(|hold| state.holds.get(*hold).unwrap().location)
+        .collect::<Vec<_>>();
+
+    let sign_config = state.sign_config.get_config();
+    let station = sign_config.best_crafting_station(
+        &state
+            .operations
+            .station_occupancy(Operation::crafting_station_location),
+        &involved_locations,
+    );
+
+    let (node_location, crafting_table_location) = match station {
+        Some(node) => (node.location, node.crafting_table.unwrap()),
+        None => {
+            release_holds(state, &created_holds);
+
+            return Err(CraftError::NoCraftingStation);
+        }
+    };
+
+    let result_stack_size = stack_size(recipe.result_id);
+    let mut results_left = recipe.result_count * crafts;
+    let mut destination_holds = vec![];
+
+    while results_left > 0 {
+        match HoldRequestFilter::EmptySlot.attempt_match(state) {
+            Ok(holds) => destination_holds.extend(holds.into_iter().map(|hold| hold.id)),
+            Err(_) => {
+                release_holds(state, &created_holds);
+                release_holds(state, &destination_holds);
+
+                return Err(CraftError::NoSpace);
+            }
+        }
+
+        results_left = results_left.saturating_sub(result_stack_size);
+    }
+
+    let op_id = state
+        .operations
+        .queue_operation_after(
+            priority,
+            OperationKind::Craft {
+                crafting_table_location,
+                node_location,
+                recipe_source_holds,
+                destination_holds: destination_holds.clone(),
+                item_id: recipe.result_id,
+                crafts,
+            },
+            depends_on,
+        )
+        .id;
+
+    for source in cells.iter().flatten() {
+        if let CellSource::Source(idx) = source {
+            sources[*idx].left -= crafts;
+        }
+    }
+
+    let mut results_left = recipe.result_count * crafts;
+    for hold in destination_holds {
+        let left = results_left.min(result_stack_size);
+        results_left -= left;
+
+        sources.push(SourceHold {
+            hold,
+            item_id: recipe.result_id,
+            left,
+            filled_by: op_id,
+        });
+    }
+
+    Ok(op_id)
+}
+
+// Crafts in the largest batch of a recipe, the smallest ingredient stack
+pub fn max_batch(recipe: &Recipe) -> u32 {
+    recipe
+        .grid
+        .iter()
+        .flatten()
+        .map(|ingredient| stack_size(*ingredient))
+        .min()
+        .unwrap_or(1)
+}
+
+// Queues batches of a recipe until it has been crafted the given number of times, batches being no
+// larger than the smallest ingredient stack or than the slots ingredients are in allow. Errors
+// come with the crafts queued before them.
+pub fn queue_recipe(
+    state: &mut State,
+    recipe: &Recipe,
+    crafts: u32,
+    priority: OperationPriority,
+    sources: &mut Vec<SourceHold>,
+    operations: &mut Vec<Uuid>,
+) -> Result<u32, (u32, CraftError)> {
+    let max_batch = max_batch(recipe);
+
+    let mut crafts_remaining = crafts;
+
+    while crafts_remaining > 0 {
+        let mut supply = Supply::new(sources);
+        supply.load_recipe(state, recipe);
+
+        let (batch, cells) = match supply.largest_batch(recipe, crafts_remaining.min(max_batch)) {
+            Ok(batch) => batch,
+            Err(ingredient) => {
+                return Err((
+                    crafts - crafts_remaining,
+                    supply.shortage(recipe, ingredient, crafts_remaining),
+                ))
+            }
+        };
+
+        match queue_batch(state, recipe, batch, &cells, &supply, priority, sources) {
+            Ok(op_id) => {
+                operations.push(op_id);
+                crafts_remaining -= batch;
+            }
+            Err(error) => return Err((crafts - crafts_remaining, error)),
+        }
+    }
+
+    Ok(crafts)
+}
+
+// Queues as many Craft operations as it takes to make count of the item
+pub fn queue_crafts(
+    state: &mut State,
+    item_id: u32,
+    count: u32,
+    priority: OperationPriority,
+) -> Result<QueuedCrafts, CraftError> {
+    let recipe = choose_recipe(state, item_id, count)?;
+
+    let mut operations = vec![];
+    let crafts = match queue_recipe(
+        state,
+        recipe,
+        count.div_ceil(recipe.result_count),
+        priority,
+        &mut vec![],
+        &mut operations,
+    ) {
+        Ok(crafts) => crafts,
+        Err((0, error)) => return Err(error),
+        // Whatever could be queued still goes ahead
+        Err((crafts, _error)) => crafts,
+    };
+
+    Ok(QueuedCrafts {
+        operations,
+        crafted: crafts * recipe.result_count,
+    })
+}
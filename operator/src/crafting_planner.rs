@@ -0,0 +1,474 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    crafting::{item_name, max_batch, queue_recipe, release_holds, CraftError, Supply},
+    data::Recipe,
+    state::{operations::OperationPriority, State},
+    types::MC_DATA,
+};
+
+// Deeper chains than this are treated as raw materials
+const MAX_DEPTH: usize = 8;
+// Upper bound on items in one plan, so a plan is always quick to make
+const MAX_VISITS: usize = 2000;
+
+#[derive(Serialize, Clone)]
+pub struct ItemCount {
+    pub item_id: u32,
+    pub name: String,
+    pub count: u32,
+}
+
+impl ItemCount {
+    fn new(item_id: u32, count: u32) -> Self {
+        ItemCount {
+            item_id,
+            name: item_name(item_id),
+            count,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct PlannedCraft {
+    pub recipe: Recipe,
+    pub crafts: u32,
+    pub ingredients: Vec<PlanNode>,
+}
+
+// How a needed count of one item is made up, from storage, crafting, or neither
+#[derive(Serialize, Clone)]
+pub struct PlanNode {
+    pub item_id: u32,
+    pub name: String,
+    pub needed: u32,
+    pub from_storage: u32,
+    pub craft: Option<PlannedCraft>,
+    pub missing: u32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PlanStep {
+    pub item_id: u32,
+    pub name: String,
+    pub recipe: Recipe,
+    pub crafts: u32,
+    // Craft operations the step is queued as, full size batches if the plan is missing anything
+    pub batches: u32,
+}
+
+#[derive(Serialize)]
+pub struct CraftPlan {
+    pub tree: PlanNode,
+    // Every craft in the tree, with each step coming after the steps it takes ingredients from
+    pub steps: Vec<PlanStep>,
+    pub consumed: Vec<ItemCount>,
+    pub missing: Vec<ItemCount>,
+}
+
+#[derive(Serialize)]
+pub struct QueuedPlan {
+    pub plan: CraftPlan,
+    pub operations: Vec<Uuid>,
+}
+
+impl PlanNode {
+    fn collect_steps(&self, steps: &mut Vec<PlanStep>) {
+        if let Some(craft) = &self.craft {
+            for ingredient in craft.ingredients.iter() {
+                ingredient.collect_steps(steps);
+            }
+
+            steps.push(PlanStep {
+                item_id: self.item_id,
+                name: self.name.clone(),
+                recipe: craft.recipe.clone(),
+                crafts: craft.crafts,
+                batches: craft.crafts.div_ceil(max_batch(&craft.recipe)),
+            });
+        }
+    }
+
+    fn collect_totals(&self, consumed: &mut HashMap<u32, u32>, missing: &mut HashMap<u32, u32>) {
+        if self.from_storage > 0 {
+            *consumed.entry(self.item_id).or_insert(0) += self.from_storage;
+        }
+
+        if self.missing > 0 {
+            *missing.entry(self.item_id).or_insert(0) += self.missing;
+        }
+
+        if let Some(craft) = &self.craft {
+            for ingredient in craft.ingredients.iter() {
+                ingredient.collect_totals(consumed, missing);
+            }
+        }
+    }
+}
+
+// Items that are free to be used, which crafts in the plan take from and add their leftovers to
+type Stock = HashMap<u32, u32>;
+
+// Unheld plain items in storage, the same slots crafting takes ingredients from
+fn stock_from_inventory(state: &State) -> Stock {
+    let mut stock = Stock::new();
+
+    for (location, slot, item, _open_from) in state.inventories.iter_slots() {
+        if let Some(item) = item {
+            if item.nbt.is_null() && state.holds.existing_hold(location, slot as u32).is_none() {
+                *stock.entry(item.item_id).or_insert(0) += item.count;
+            }
+        }
+    }
+
+    stock
+}
+
+// How well storage covers a recipe's direct ingredients, used to pick one recipe per item rather
+// than searching every alternative
+fn recipe_score(
+    recipe: &Recipe,
+    crafts: u32,
+    stock: &Stock,
+    crafting_chain: &[u32],
+) -> (bool, usize) {
+    let ingredient_counts = recipe.ingredient_counts();
+
+    // Recipes made from an item already being crafted further up, like dye recolouring, go in
+    // circles
+    let acyclic = ingredient_counts
+        .keys()
+        .all(|ingredient| !crafting_chain.contains(ingredient));
+    let covered = ingredient_counts
+        .iter()
+        .filter(|(ingredient, per_craft)| {
+            stock.get(ingredient).copied().unwrap_or(0) >= *per_craft * crafts
+        })
+        .count();
+
+    (acyclic, covered)
+}
+
+struct Planner {
+    stock: Stock,
+    crafting_chain: Vec<u32>,
+    // Items planned so far, once this reaches MAX_VISITS everything else is treated as missing
+    visits: usize,
+}
+
+impl Planner {
+    fn plan_item(&mut self, item_id: u32, needed: u32) -> PlanNode {
+        self.visits += 1;
+
+        let available = self.stock.entry(item_id).or_insert(0);
+        let from_storage = needed.min(*available);
+        *available -= from_storage;
+
+        let mut node = PlanNode {
+            item_id,
+            name: item_name(item_id),
+            needed,
+            from_storage,
+            craft: None,
+            missing: needed - from_storage,
+        };
+
+        let to_craft = node.missing;
+
+        if to_craft == 0
+            || self.crafting_chain.contains(&item_id)
+            || self.crafting_chain.len() >= MAX_DEPTH
+            || self.visits >= MAX_VISITS
+        {
+            return node;
+        }
+
+        // The first recipe among those covering the most ingredients from storage
+        let recipe = MC_DATA.recipes_by_result.get(&item_id).and_then(|recipes| {
+            recipes.iter().rev().max_by_key(|recipe| {
+                recipe_score(
+                    recipe,
+                    to_craft.div_ceil(recipe.result_count),
+                    &self.stock,
+                    &self.crafting_chain,
+                )
+            })
+        });
+
+        let recipe = match recipe {
+            Some(recipe) => recipe,
+            None => return node,
+        };
+
+        let crafts = to_craft.div_ceil(recipe.result_count);
+
+        let mut ingredient_counts = recipe.ingredient_counts().into_iter().collect::<Vec<_>>();
+        ingredient_counts.sort();
+
+        self.crafting_chain.push(item_id);
+
+        let ingredients = ingredient_counts
+            .into_iter()
+            .map(|(ingredient, per_craft)| self.plan_item(ingredient, per_craft * crafts))
+            .collect::<Vec<_>>();
+
+        self.crafting_chain.pop();
+
+        *self.stock.entry(item_id).or_insert(0) += crafts * recipe.result_count - to_craft;
+
+        node.craft = Some(PlannedCraft {
+            recipe: recipe.clone(),
+            crafts,
+            ingredients,
+        });
+        node.missing = 0;
+
+        node
+    }
+}
+
+impl CraftPlan {
+    // Craft operations queueing the plan takes, one per batch of each step
+    pub fn operation_count(&self) -> usize {
+        self.steps.iter().map(|step| step.batches as usize).sum()
+    }
+}
+
+fn item_counts(counts: HashMap<u32, u32>) -> Vec<ItemCount> {
+    let mut counts = counts
+        .into_iter()
+        .map(|(item_id, count)| ItemCount::new(item_id, count))
+        .collect::<Vec<_>>();
+
+    counts.sort_by_key(|item_count| item_count.item_id);
+
+    counts
+}
+
+// Plans the crafts it takes to make count of the item from what is in storage, without holding
+// or queueing anything
+pub fn plan_crafts(state: &State, item_id: u32, count: u32) -> CraftPlan {
+    let mut stock = stock_from_inventory(state);
+
+    // The target is always crafted, even if storage already has some
+    stock.remove(&item_id);
+
+    let mut planner = Planner {
+        stock,
+        crafting_chain: vec![],
+        visits: 0,
+    };
+    let tree = planner.plan_item(item_id, count);
+
+    let mut steps = vec![];
+    tree.collect_steps(&mut steps);
+
+    let mut consumed = HashMap::new();
+    let mut missing = HashMap::new();
+    tree.collect_totals(&mut consumed, &mut missing);
+
+    // Storage can hold enough of an ingredient in total but in stacks too small for the batches
+    // to take it from, so a plan is only complete once its steps fit the slots in storage
+    if missing.is_empty() {
+        let mut supply = Supply::default();
+
+        for step in steps.iter_mut() {
+            match supply.use_recipe(state, &step.recipe, step.crafts) {
+                Ok(batches) => step.batches = batches,
+                Err((crafted, ingredient)) => {
+                    let needed =
+                        step.recipe.ingredient_counts()[&ingredient] * (step.crafts - crafted);
+
+                    missing.insert(ingredient, needed - supply.available(ingredient));
+                    break;
+                }
+            }
+        }
+    }
+
+    CraftPlan {
+        tree,
+        steps,
+        consumed: item_counts(consumed),
+        missing: item_counts(missing),
+    }
+}
+
+// Queues every step of a complete plan, with each Craft operation waiting on the operations
+// that make its ingredients. Nothing is left queued if any step cannot be.
+pub fn queue_plan(
+    state: &mut State,
+    item_id: u32,
+    count: u32,
+    priority: OperationPriority,
+) -> Result<QueuedPlan, CraftError> {
+    let plan = plan_crafts(state, item_id, count);
+
+    if let Some(missing) = plan.missing.first() {
+        let needed = plan
+            .consumed
+            .iter()
+            .find(|consumed| consumed.item_id == missing.item_id)
+            .map_or(0, |consumed| consumed.count);
+
+        return Err(CraftError::MissingIngredient {
+            name: missing.name.clone(),
+            needed: needed + missing.count,
+            available: needed,
+        });
+    }
+
+    let mut sources = vec![];
+    let mut operations = vec![];
+
+    for step in plan.steps.iter() {
+        let queued = queue_recipe(
+            state,
+            &step.recipe,
+            step.crafts,
+            priority,
+            &mut sources,
+            &mut operations,
+        );
+
+        let error = match queued {
+            Ok(_crafts) => continue,
+            Err((_crafts, error)) => error,
+        };
+
+        for op_id in operations {
+            if let Some(op) = state.operations.remove_pending_operation(op_id) {
+                release_holds(state, &op.holds());
+            }
+        }
+
+        return Err(error);
+    }
+
+    Ok(QueuedPlan { plan, operations })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde_json::Value;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        crafting::item_id_by_name,
+        types::{ContainerKind, Dimension, Inventory, Item, Location, Vec3},
+    };
+
+    fn id(name: &str) -> u32 {
+        item_id_by_name(name).unwrap()
+    }
+
+    // A state with one chest holding stacks of the given sizes
+    fn state_with(stacks: &[(&str, u32)]) -> State {
+        let mut slots = stacks
+            .iter()
+            .map(|(name, count)| {
+                Some(Item {
+                    item_id: id(name),
+                    count: *count,
+                    metadata: 0,
+                    nbt: Arc::new(Value::Null),
+                    stack_size: 64,
+                    stackable_hash: id(name) as u64,
+                    shulker_data: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        slots.resize(27, None);
+
+        let mut state = State::default();
+        state.inventories.set_inventory_at(
+            Location {
+                vec3: Vec3 { x: 0, y: 64, z: 0 },
+                dim: Dimension::Overworld,
+            },
+            Inventory {
+                kind: ContainerKind::Chest,
+                slots,
+                scanned_at: Utc::now(),
+                open_from: Vec3 { x: 0, y: 65, z: 0 },
+                predicted: false,
+            },
+        );
+
+        state
+    }
+
+    fn counts(item_counts: &[ItemCount]) -> Vec<(u32, u32)> {
+        item_counts
+            .iter()
+            .map(|item_count| (item_count.item_id, item_count.count))
+            .collect()
+    }
+
+    #[test]
+    fn crafts_from_storage() {
+        let state = state_with(&[("oak_planks", 64)]);
+
+        let plan = plan_crafts(&state, id("stick"), 4);
+
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(plan.steps[0].crafts, 1);
+        assert_eq!(plan.operation_count(), 1);
+        assert_eq!(counts(&plan.consumed), vec![(id("oak_planks"), 2)]);
+        assert!(plan.missing.is_empty());
+    }
+
+    #[test]
+    fn crafts_intermediate_items_first() {
+        let state = state_with(&[("oak_log", 1)]);
+
+        let plan = plan_crafts(&state, id("stick"), 8);
+
+        let steps = plan
+            .steps
+            .iter()
+            .map(|step| (step.item_id, step.crafts))
+            .collect::<Vec<_>>();
+
+        assert_eq!(steps, vec![(id("oak_planks"), 1), (id("stick"), 2)]);
+        assert_eq!(counts(&plan.consumed), vec![(id("oak_log"), 1)]);
+        assert!(plan.missing.is_empty());
+    }
+
+    #[test]
+    fn reports_what_storage_lacks() {
+        let state = state_with(&[]);
+
+        let plan = plan_crafts(&state, id("stick"), 4);
+
+        assert!(plan.consumed.is_empty());
+        assert!(!plan.missing.is_empty());
+    }
+
+    #[test]
+    fn splits_crafts_over_partial_stacks() {
+        let state = state_with(&[("oak_planks", 3), ("oak_planks", 3), ("oak_planks", 3)]);
+
+        // Each of the two cells takes every craft's worth from one stack, so four crafts take a
+        // batch of three and a batch of one
+        let plan = plan_crafts(&state, id("stick"), 16);
+
+        assert_eq!(plan.steps[0].crafts, 4);
+        assert_eq!(plan.operation_count(), 2);
+        assert!(plan.missing.is_empty());
+    }
+
+    #[test]
+    fn reports_stacks_too_small_to_craft_from() {
+        let state = state_with(&[("oak_planks", 3), ("oak_planks", 1)]);
+
+        // Enough planks in total, but the second craft only has the single plank left to take
+        let plan = plan_crafts(&state, id("stick"), 8);
+
+        assert_eq!(counts(&plan.missing), vec![(id("oak_planks"), 1)]);
+    }
+}
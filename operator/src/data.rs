@@ -5,6 +5,12 @@ pub struct McData {
     pub items: Vec<McDataItem>,
     pub items_by_id: HashMap<u32, McDataItem>,
     pub items_by_name: HashMap<String, McDataItem>,
+    pub recipes_by_result: HashMap<u32, Vec<Recipe>>,
+    pub compressions: Vec<Compression>,
+    // Key: input item id, value: item id smelting it gives
+    pub smelting_results: HashMap<u32, u32>,
+    // Key: fuel item id, value: ticks it burns for, smelting one item takes 200
+    pub fuel_burn_ticks: HashMap<u32, u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -17,6 +23,137 @@ pub struct McDataItem {
     pub stack_size: u32,
 }
 
+#[derive(Deserialize)]
+struct McDataRecipeResult {
+    id: u32,
+    count: u32,
+}
+
+#[derive(Deserialize)]
+struct McDataRecipe {
+    #[serde(rename = "inShape")]
+    in_shape: Option<Vec<Vec<Option<u32>>>>,
+    ingredients: Option<Vec<u32>>,
+    result: McDataRecipeResult,
+}
+
+// A crafting table recipe, with ingredients laid out row by row in the 3x3 grid
+#[derive(Serialize, Clone, Debug)]
+pub struct Recipe {
+    pub grid: [Option<u32>; 9],
+    pub result_id: u32,
+    pub result_count: u32,
+}
+
+impl Recipe {
+    fn from_mc_data(recipe: McDataRecipe) -> Option<Self> {
+        let mut grid = [None; 9];
+
+        if let Some(in_shape) = recipe.in_shape {
+            for (row_idx, row) in in_shape.iter().enumerate() {
+                for (col_idx, ingredient) in row.iter().enumerate() {
+                    if row_idx >= 3 || col_idx >= 3 {
+                        return None;
+                    }
+
+                    grid[row_idx * 3 + col_idx] = *ingredient;
+                }
+            }
+        } else {
+            let ingredients = recipe.ingredients?;
+
+            if ingredients.len() > 9 {
+                return None;
+            }
+
+            for (idx, ingredient) in ingredients.into_iter().enumerate() {
+                grid[idx] = Some(ingredient);
+            }
+        }
+
+        Some(Recipe {
+            grid,
+            result_id: recipe.result.id,
+            result_count: recipe.result.count,
+        })
+    }
+
+    // Count of each ingredient used by a single craft
+    pub fn ingredient_counts(&self) -> HashMap<u32, u32> {
+        let mut counts = HashMap::new();
+
+        for ingredient in self.grid.iter().flatten() {
+            *counts.entry(*ingredient).or_insert(0) += 1;
+        }
+
+        counts
+    }
+}
+
+#[derive(Deserialize)]
+struct SmeltingRecipe {
+    input: String,
+    result: String,
+}
+
+// Furnace recipes and fuels by item name, which minecraft-data does not include. Fuels that leave
+// a container behind, like lava buckets, are left out since agents can't take it back out.
+#[derive(Deserialize)]
+struct SmeltingData {
+    recipes: Vec<SmeltingRecipe>,
+    fuels: HashMap<String, u32>,
+}
+
+// An item nine of which craft into a block that crafts back into nine of the item
+#[derive(Serialize, Clone, Debug)]
+pub struct Compression {
+    pub item_id: u32,
+    pub block_id: u32,
+    pub compress: Recipe,
+    pub decompress: Recipe,
+}
+
+impl Compression {
+    fn find_all(recipes_by_result: &HashMap<u32, Vec<Recipe>>) -> Vec<Self> {
+        let mut compressions = vec![];
+
+        for (block_id, recipes) in recipes_by_result.iter() {
+            let compress = recipes.iter().find(|recipe| {
+                recipe.result_count == 1
+                    && recipe.grid[0].is_some()
+                    && recipe.grid.iter().all(|cell| *cell == recipe.grid[0])
+            });
+
+            let compress = match compress {
+                Some(compress) => compress,
+                None => continue,
+            };
+
+            let item_id = compress.grid[0].unwrap();
+
+            let decompress = recipes_by_result.get(&item_id).and_then(|recipes| {
+                recipes.iter().find(|recipe| {
+                    recipe.result_count == 9
+                        && recipe.ingredient_counts() == HashMap::from([(*block_id, 1)])
+                })
+            });
+
+            if let Some(decompress) = decompress {
+                compressions.push(Compression {
+                    item_id,
+                    block_id: *block_id,
+                    compress: compress.clone(),
+                    decompress: decompress.clone(),
+                });
+            }
+        }
+
+        compressions.sort_by_key(|compression| compression.item_id);
+
+        compressions
+    }
+}
+
 impl McData {
     pub fn init() -> Self {
         let items: Vec<McDataItem> =
@@ -29,10 +166,49 @@ impl McData {
             items_by_name.insert(item.name.clone(), item.clone());
         }
 
+        let recipes: HashMap<u32, Vec<McDataRecipe>> =
+            serde_json::from_str(include_str!("../assets/minecraft-data/recipes.json")).unwrap();
+        let recipes_by_result: HashMap<u32, Vec<Recipe>> = recipes
+            .into_iter()
+            .map(|(result_id, recipes)| {
+                (
+                    result_id,
+                    recipes
+                        .into_iter()
+                        .filter_map(Recipe::from_mc_data)
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let compressions = Compression::find_all(&recipes_by_result);
+
+        let smelting: SmeltingData =
+            serde_json::from_str(include_str!("../assets/smelting.json")).unwrap();
+        let smelting_results = smelting
+            .recipes
+            .iter()
+            .filter_map(|recipe| {
+                Some((
+                    items_by_name.get(&recipe.input)?.id,
+                    items_by_name.get(&recipe.result)?.id,
+                ))
+            })
+            .collect();
+        let fuel_burn_ticks = smelting
+            .fuels
+            .iter()
+            .filter_map(|(name, ticks)| Some((items_by_name.get(name)?.id, *ticks)))
+            .collect();
+
         Self {
             items,
             items_by_id,
             items_by_name,
+            recipes_by_result,
+            compressions,
+            smelting_results,
+            fuel_burn_ticks,
         }
     }
 }
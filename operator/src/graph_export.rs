@@ -0,0 +1,230 @@
+use serde_json::{json, Value};
+
+use crate::{
+    state::sign_config::CompiledSignConfig,
+    types::{Dimension, Vec2, Vec3},
+};
+
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+
+    format!("\"{}\"", escaped)
+}
+
+fn format_vec3(vec3: Vec3) -> String {
+    format!("({}, {}, {})", vec3.x, vec3.y, vec3.z)
+}
+
+// Renders nodes, complexes and every edge the pathfinder considers as a Graphviz graph, with one
+// cluster per dimension
+pub fn render_dot(sign_config: &CompiledSignConfig) -> String {
+    let mut dot = String::from("digraph sss {\n");
+
+    for dim in [
+        Dimension::Overworld,
+        Dimension::TheNether,
+        Dimension::TheEnd,
+    ] {
+        let mut nodes = sign_config
+            .nodes
+            .values()
+            .filter(|node| node.location.dim == dim)
+            .collect::<Vec<_>>();
+        let mut complexes = sign_config
+            .complexes
+            .values()
+            .filter(|complex| complex.dimension() == dim)
+            .collect::<Vec<_>>();
+
+        if nodes.is_empty() && complexes.is_empty() {
+            continue;
+        }
+
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+        complexes.sort_by(|a, b| a.name().cmp(b.name()));
+
+        dot += &format!("  subgraph {} {{\n", quote(&format!("cluster_{:?}", dim)));
+        dot += &format!("    label={};\n", quote(&format!("{:?}", dim)));
+
+        for node in nodes {
+            let mut label = vec![node.name.clone(), format_vec3(node.location.vec3)];
+
+            if let Some(pickup) = node.pickup {
+                label.push(format!("pickup {}", format_vec3(pickup)));
+            }
+            if let Some(dropoff) = node.dropoff {
+                label.push(format!("drop-off {}", format_vec3(dropoff)));
+            }
+            if node.shulker_station {
+                label.push("shulker station".to_owned());
+            }
+            if let Some(portal) = &node.portal {
+                label.push(format!("portal {}", format_vec3(portal.vec3)));
+            }
+
+            dot += &format!(
+                "    {} [shape=ellipse label={}];\n",
+                quote(&node.name),
+                quote(&label.join("\n"))
+            );
+        }
+
+        for complex in complexes {
+            let (corner_a, corner_b) = complex.footprint();
+
+            dot += &format!(
+                "    {} [shape=box3d label={}];\n",
+                quote(&format!("complex:{}", complex.name())),
+                quote(&format!(
+                    "{}\n({}, {}) to ({}, {})",
+                    complex.name(),
+                    corner_a.x,
+                    corner_a.z,
+                    corner_b.x,
+                    corner_b.z
+                ))
+            );
+        }
+
+        dot += "  }\n";
+    }
+
+    let mut nodes = sign_config.nodes.values().collect::<Vec<_>>();
+    nodes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for node in nodes {
+        for link in node.links.iter() {
+            dot += &format!(
+                "  {} -> {} [label={}];\n",
+                quote(&node.name),
+                quote(&link.node_name),
+                quote(&link.cost.to_string())
+            );
+        }
+
+        if let Some(portal) = &node.portal {
+            dot += &format!(
+                "  {} -> {} [style=dashed label=\"portal\"];\n",
+                quote(&node.name),
+                quote(&portal.destination_node_name)
+            );
+        }
+    }
+
+    dot += "}\n";
+
+    dot
+}
+
+fn point(vec3: Vec3, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": { "type": "Point", "coordinates": [vec3.x, vec3.z] },
+        "properties": properties,
+    })
+}
+
+// Block corners are used, so the polygon covers the full area of both corner blocks
+fn footprint_polygon((a, b): (Vec2, Vec2), properties: Value) -> Value {
+    let (x1, x2) = (a.x.min(b.x), a.x.max(b.x) + 1);
+    let (z1, z2) = (a.z.min(b.z), a.z.max(b.z) + 1);
+
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": [[[x1, z1], [x2, z1], [x2, z2], [x1, z2], [x1, z1]]],
+        },
+        "properties": properties,
+    })
+}
+
+// Renders a single dimension as a GeoJSON feature collection, using x / z as the plane
+pub fn render_geojson(sign_config: &CompiledSignConfig, dim: Dimension) -> Value {
+    let mut features = vec![];
+
+    for node in sign_config
+        .nodes
+        .values()
+        .filter(|node| node.location.dim == dim)
+    {
+        features.push(point(
+            node.location.vec3,
+            json!({
+                "kind": "node",
+                "name": node.name,
+                "y": node.location.vec3.y,
+                "shulker_station": node.shulker_station,
+            }),
+        ));
+
+        if let Some(pickup) = node.pickup {
+            features.push(point(
+                pickup,
+                json!({ "kind": "pickup", "node": node.name, "y": pickup.y }),
+            ));
+        }
+
+        if let Some(dropoff) = node.dropoff {
+            features.push(point(
+                dropoff,
+                json!({ "kind": "dropoff", "node": node.name, "y": dropoff.y }),
+            ));
+        }
+
+        if let Some(portal) = &node.portal {
+            features.push(point(
+                portal.vec3,
+                json!({
+                    "kind": "portal",
+                    "node": node.name,
+                    "destination": portal.destination_node_name,
+                    "y": portal.vec3.y,
+                }),
+            ));
+        }
+
+        for link in node.links.iter() {
+            let destination = match sign_config.nodes.get(&link.node_name) {
+                Some(destination) => destination,
+                None => continue,
+            };
+
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": [
+                        [node.location.vec3.x, node.location.vec3.z],
+                        [destination.location.vec3.x, destination.location.vec3.z],
+                    ],
+                },
+                "properties": {
+                    "kind": "link",
+                    "from": node.name,
+                    "to": link.node_name,
+                    "cost": link.cost,
+                },
+            }));
+        }
+    }
+
+    for complex in sign_config
+        .complexes
+        .values()
+        .filter(|complex| complex.dimension() == dim)
+    {
+        features.push(footprint_polygon(
+            complex.footprint(),
+            json!({ "kind": "complex", "name": complex.name() }),
+        ));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
@@ -2,10 +2,17 @@
 extern crate log;
 
 mod api;
+mod compression;
 mod config;
+mod crafting;
+mod crafting_planner;
 mod data;
+mod graph_export;
 mod pathfinding;
+mod prediction;
 mod services;
+mod sign_config_file;
+mod smelting;
 mod state;
 mod stats;
 mod types;
@@ -26,11 +33,16 @@ use crate::{
     services::{
         aborted_operation_recovery::AbortedOperationRecoveryService,
         agent_expiration::AgentExpirationService, alert_expiration::AlertExpirationService,
-        defragger::DefraggerService, hold_expiration::HoldExpirationService,
-        inventory_scanner::InventoryScannerService, node_scanner::NodeScannerService,
-        operation_expiration::OperationExpirationService, service::Service,
+        compressor::CompressorService, defragger::DefraggerService,
+        discrepancy_expiration::DiscrepancyExpirationService,
+        hold_expiration::HoldExpirationService, inventory_scanner::InventoryScannerService,
+        node_scanner::NodeScannerService, operation_expiration::OperationExpirationService,
+        path_penalty_decay::PathPenaltyDecayService, service::Service,
         shulker_loader::ShulkerLoaderService, shulker_unloader::ShulkerUnloaderService,
+        sign_config_watch::SignConfigWatchService, smelter::SmelterService,
+        stock_keeper::StockKeeperService, travel_time_refresh::TravelTimeRefreshService,
     },
+    sign_config_file::{DeclaredSignConfig, SignConfigFileError},
     state::StateData,
 };
 
@@ -40,6 +52,8 @@ enum StartupError {
     FigmentError(figment::Error),
     #[error(transparent)]
     CreateServerError(std::io::Error),
+    #[error(transparent)]
+    SignConfigFile(SignConfigFileError),
 }
 
 #[actix_web::main]
@@ -51,6 +65,21 @@ async fn main() -> Result<(), StartupError> {
 
     let state: StateData = web::Data::new(Mutex::new(Default::default()));
 
+    if let Some(path) = &config.sign_config_file {
+        let declared = DeclaredSignConfig::read(path).map_err(StartupError::SignConfigFile)?;
+        info!(
+            "Loaded {} declared signs from {}",
+            declared.signs.len(),
+            path
+        );
+
+        state
+            .lock()
+            .unwrap()
+            .sign_config
+            .set_declared_signs(declared.signs);
+    }
+
     let bg_state = state.clone();
     let bg_config = config.clone();
 
@@ -69,6 +98,13 @@ async fn main() -> Result<(), StartupError> {
             Box::new(ShulkerLoaderService::new(&config)),
             Box::new(OperationExpirationService::new(&config)),
             Box::new(AlertExpirationService::new(&config)),
+            Box::new(DiscrepancyExpirationService::new(&config)),
+            Box::new(PathPenaltyDecayService::new(&config)),
+            Box::new(TravelTimeRefreshService::new(&config)),
+            Box::new(SignConfigWatchService::new(&config)),
+            Box::new(StockKeeperService::new(&config)),
+            Box::new(CompressorService::new(&config)),
+            Box::new(SmelterService::new(&config)),
         ];
 
         loop {
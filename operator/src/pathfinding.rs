@@ -1,15 +1,26 @@
-use pathfinding::directed::bfs::bfs;
-use serde::Serialize;
+use pathfinding::directed::dijkstra::dijkstra;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
 use thiserror::Error;
 
 use crate::{
     state::{
-        sign_config::{CompiledSignConfig, StorageComplex},
-        State,
+        path_penalties::edge_key,
+        sign_config::{walking_cost, CompiledSignConfig, StorageComplex},
     },
     types::{Dimension, Location, Vec2, Vec3},
 };
 
+// Extra cost of travelling through a portal, on top of walking to it
+const PORTAL_COST: u32 = 16;
+
 #[derive(Error, Debug, Serialize)]
 pub enum PathfindingError {
     #[error("No path available")]
@@ -27,6 +38,7 @@ fn is_in_complex(loc: Location, sign_config: &CompiledSignConfig) -> Option<Stri
                 name,
                 origin,
                 height,
+                ..
             } => {
                 if loc.dim != *dimension {
                     continue;
@@ -45,12 +57,20 @@ fn is_in_complex(loc: Location, sign_config: &CompiledSignConfig) -> Option<Stri
                 name,
                 y_level,
                 bounds,
+                layers,
+                stride,
+                open_from,
             } => {
                 if loc.dim != *dimension {
                     continue;
                 }
 
-                if loc.vec3.y != y_level + 1 {
+                let standing_on_layer =
+                    StorageComplex::floor_layer_levels(*y_level, *layers, *stride)
+                        .into_iter()
+                        .any(|layer_y| loc.vec3.y == layer_y + open_from.offset().y);
+
+                if !standing_on_layer {
                     continue;
                 }
 
@@ -60,6 +80,30 @@ fn is_in_complex(loc: Location, sign_config: &CompiledSignConfig) -> Option<Stri
                     return Some(name.clone());
                 }
             }
+            StorageComplex::Wall {
+                dimension,
+                name,
+                origin,
+                width,
+                facing,
+                standoff,
+                ..
+            } => {
+                if loc.dim != *dimension {
+                    continue;
+                }
+
+                if loc.vec3.y != origin.y {
+                    continue;
+                }
+
+                let (row_start, row_end) =
+                    StorageComplex::wall_standing_row(*origin, *width, *facing, *standoff);
+
+                if Vec2::from(loc.vec3).contained_by(row_start.into(), row_end.into(), 1) {
+                    return Some(name.clone());
+                }
+            }
         }
     }
 
@@ -81,7 +125,7 @@ fn is_exactly_in_between(a: Vec3, b: Vec3, c: Vec3) -> bool {
     return equality_count >= 2;
 }
 
-fn find_aligned_node(start_loc: Location, sign_config: &CompiledSignConfig) -> Option<String> {
+pub fn find_aligned_node(start_loc: Location, sign_config: &CompiledSignConfig) -> Option<String> {
     if let Some(complex) = is_in_complex(start_loc, &sign_config) {
         return Some(complex);
     }
@@ -139,6 +183,9 @@ enum PfNode {
     Normal { node: String },
 }
 
+// Shortest path between two nodes, if there is one
+type PfPath = Option<(Vec<PfNode>, u32)>;
+
 #[derive(Serialize)]
 pub enum PfResultNode {
     Vec(Vec3),
@@ -148,136 +195,365 @@ pub enum PfResultNode {
     },
 }
 
+// An edge of the pathfinding graph, by node name
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PathEdge {
+    pub source_node: String,
+    pub destination_node: String,
+}
+
+// Path nodes for an agent to travel to in turn, with the graph edges covered getting to each
+#[derive(Serialize)]
+pub struct FoundPath {
+    pub path: Vec<PfResultNode>,
+    pub edges: Vec<Vec<PathEdge>>,
+}
+
+#[derive(Default)]
+pub struct PathCacheMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PathCacheMetrics {
+    pub fn stats(&self, cached_paths: usize) -> PathCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+
+        PathCacheStats {
+            hits,
+            misses,
+            hit_rate: (hits + misses > 0).then(|| hits as f64 / (hits + misses) as f64),
+            cached_paths,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PathCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: Option<f64>,
+    pub cached_paths: usize,
+}
+
+// Cost assumed for crossing between dimensions with no known portal and no coordinate scale
+const UNKNOWN_PORTAL_COST: u32 = 1000;
+
+struct PortalLeg {
+    entrance: Location,
+    exit: Location,
+}
+
+// Estimates travel costs between locations. Trips between dimensions are routed through at most
+// two known portals (e.g. overworld -> nether -> overworld), falling back to the 8:1 nether
+// coordinate scale when no suitable portal is known
+pub struct TravelEstimator {
+    portal_legs: Vec<PortalLeg>,
+}
+
+impl TravelEstimator {
+    pub fn new(sign_config: &CompiledSignConfig) -> Self {
+        let portal_legs = sign_config
+            .nodes
+            .values()
+            .filter_map(|node| {
+                let portal = node.portal.as_ref()?;
+                let destination_node = sign_config.nodes.get(&portal.destination_node_name)?;
+
+                Some(PortalLeg {
+                    entrance: Location {
+                        vec3: portal.vec3,
+                        dim: node.location.dim,
+                    },
+                    exit: destination_node.location,
+                })
+            })
+            .collect();
+
+        TravelEstimator { portal_legs }
+    }
+
+    pub fn estimate(&self, from: Location, to: Location) -> u32 {
+        let walk =
+            |a: Location, b: Location| (a.dim == b.dim).then(|| walking_cost(a.vec3, b.vec3));
+
+        let mut candidates: Vec<u32> = walk(from, to).into_iter().collect();
+
+        for first in self
+            .portal_legs
+            .iter()
+            .filter(|leg| leg.entrance.dim == from.dim)
+        {
+            let to_first = walking_cost(from.vec3, first.entrance.vec3) + PORTAL_COST;
+
+            if let Some(rest) = walk(first.exit, to) {
+                candidates.push(to_first + rest);
+            }
+
+            for second in self
+                .portal_legs
+                .iter()
+                .filter(|leg| leg.entrance.dim == first.exit.dim)
+            {
+                if let Some(rest) = walk(second.exit, to) {
+                    candidates.push(
+                        to_first
+                            + walking_cost(first.exit.vec3, second.entrance.vec3)
+                            + PORTAL_COST
+                            + rest,
+                    );
+                }
+            }
+        }
+
+        candidates.into_iter().min().unwrap_or_else(|| {
+            from.scaled_to(to.dim)
+                .map_or(UNKNOWN_PORTAL_COST, |scaled| {
+                    walking_cost(scaled.vec3, to.vec3) + PORTAL_COST
+                })
+        })
+    }
+}
+
+// Pathfinding graph for a single generation of the sign config, with node to node paths
+// memoized as they are requested
+pub struct PathGraph {
+    sign_config: Arc<CompiledSignConfig>,
+    adjacency: HashMap<PfNode, Vec<(PfNode, u32)>>,
+    paths: Mutex<HashMap<(String, String), PfPath>>,
+    metrics: Arc<PathCacheMetrics>,
+}
+
+impl PathGraph {
+    pub fn new(
+        sign_config: Arc<CompiledSignConfig>,
+        edge_penalties: &HashMap<(String, String), u32>,
+        learned_edge_costs: &HashMap<(String, String), u32>,
+        metrics: Arc<PathCacheMetrics>,
+    ) -> Self {
+        let mut adjacency = HashMap::new();
+
+        for (name, config_node) in sign_config.nodes.iter() {
+            let mut connected_nodes: Vec<(PfNode, u32)> = config_node
+                .links
+                .iter()
+                .filter(|link| sign_config.nodes.contains_key(&link.node_name))
+                .map(|link| {
+                    let penalty = edge_penalties
+                        .get(&edge_key(name, &link.node_name))
+                        .copied()
+                        .unwrap_or(0);
+                    let cost = learned_edge_costs
+                        .get(&(name.clone(), link.node_name.clone()))
+                        .copied()
+                        .unwrap_or(link.cost);
+
+                    (
+                        PfNode::Normal {
+                            node: link.node_name.clone(),
+                        },
+                        cost + penalty,
+                    )
+                })
+                .collect();
+
+            // Portals to nodes that haven't been scanned yet lead nowhere
+            if let Some(portal) = config_node.portal.as_ref().filter(|portal| {
+                sign_config
+                    .nodes
+                    .contains_key(&portal.destination_node_name)
+            }) {
+                let portal_node = PfNode::Portal {
+                    source_node: name.clone(),
+                };
+                let penalty = edge_penalties
+                    .get(&edge_key(name, &portal.destination_node_name))
+                    .copied()
+                    .unwrap_or(0);
+                let cost = learned_edge_costs
+                    .get(&(name.clone(), portal.destination_node_name.clone()))
+                    .copied()
+                    .unwrap_or_else(|| {
+                        walking_cost(config_node.location.vec3, portal.vec3) + PORTAL_COST
+                    });
+
+                connected_nodes.push((portal_node.clone(), cost + penalty));
+
+                adjacency.insert(
+                    portal_node,
+                    vec![(
+                        PfNode::Normal {
+                            node: portal.destination_node_name.clone(),
+                        },
+                        0,
+                    )],
+                );
+            }
+
+            adjacency.insert(PfNode::Normal { node: name.clone() }, connected_nodes);
+        }
+
+        PathGraph {
+            sign_config,
+            adjacency,
+            paths: Default::default(),
+            metrics,
+        }
+    }
+
+    pub fn sign_config(&self) -> &CompiledSignConfig {
+        &self.sign_config
+    }
+
+    pub fn cached_paths(&self) -> usize {
+        self.paths.lock().unwrap().len()
+    }
+
+    fn shortest_path(&self, starting_node: &str, ending_node: &str) -> PfPath {
+        let key = (starting_node.to_owned(), ending_node.to_owned());
+
+        if let Some(path) = self.paths.lock().unwrap().get(&key) {
+            self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+            return path.clone();
+        }
+
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+
+        // Link costs and learned costs can be below the straight line distance, so there is no
+        // admissible distance heuristic to search with
+        let path = dijkstra(
+            &PfNode::Normal {
+                node: key.0.clone(),
+            },
+            |node| self.adjacency.get(node).cloned().unwrap_or_default(),
+            |node| match &node {
+                PfNode::Normal { node } => *node == key.1,
+                _ => false,
+            },
+        );
+
+        self.paths.lock().unwrap().insert(key, path.clone());
+
+        path
+    }
+
+    // Cost of the cheapest route between two nodes
+    pub fn node_cost(&self, starting_node: &str, ending_node: &str) -> Option<u32> {
+        if starting_node == ending_node {
+            return Some(0);
+        }
+
+        self.shortest_path(starting_node, ending_node)
+            .map(|(_path, cost)| cost)
+    }
+}
+
 pub fn find_path(
     start_loc: Location,
     end_loc: Location,
-    state: &State,
-) -> Result<Vec<PfResultNode>, PathfindingError> {
-    let sign_config = state.sign_config.get_config();
+    graph: &PathGraph,
+) -> Result<FoundPath, PathfindingError> {
+    let sign_config = &graph.sign_config;
 
-    let starting_node = find_aligned_node(start_loc, &sign_config)
+    let starting_node = find_aligned_node(start_loc, sign_config)
         .ok_or(PathfindingError::UnknownStartingLocation)?;
     let starting_config_node = sign_config.nodes.get(&starting_node).unwrap();
-    let ending_node = find_aligned_node(end_loc, &sign_config)
-        .ok_or(PathfindingError::UnknownStartingLocation)?;
+    let ending_node =
+        find_aligned_node(end_loc, sign_config).ok_or(PathfindingError::UnknownStartingLocation)?;
 
     if starting_node == ending_node {
-        return Ok(vec![PfResultNode::Vec(end_loc.vec3)]);
+        return Ok(FoundPath {
+            path: vec![PfResultNode::Vec(end_loc.vec3)],
+            edges: vec![vec![]],
+        });
     }
 
-    let path = bfs(
-        &PfNode::Normal {
-            node: starting_node,
-        },
-        |node| match &node {
-            PfNode::Normal { node } => {
-                let config_node = sign_config.nodes.get(node).unwrap();
-                let mut connected_nodes: Vec<PfNode> = sign_config
-                    .nodes
-                    .iter()
-                    .filter(|(name, sign_node)| {
-                        if node == *name {
-                            return false;
-                        }
+    let (pf_path, _cost) = graph
+        .shortest_path(&starting_node, &ending_node)
+        .ok_or(PathfindingError::NoPath)?;
 
-                        if sign_node.location.dim != config_node.location.dim {
-                            return false;
-                        }
+    let mut path = vec![(
+        PfResultNode::Vec(starting_config_node.location.vec3),
+        vec![],
+    )];
+    let mut previous_node: Option<&str> = None;
 
-                        return true;
-                    })
-                    .map(|(name, _sign_node)| PfNode::Normal {
-                        node: name.to_owned(),
+    for node in pf_path.iter() {
+        path.push(match node {
+            PfNode::Normal { node } => {
+                let config_node = sign_config.nodes.get(node).unwrap();
+                // Nodes reached through a portal are a step from its exit, not an edge
+                let edges = previous_node
+                    .map(|previous_node| PathEdge {
+                        source_node: previous_node.to_owned(),
+                        destination_node: node.clone(),
                     })
+                    .into_iter()
                     .collect();
 
-                if config_node.portal.is_some() {
-                    connected_nodes.push(PfNode::Portal {
-                        source_node: node.clone(),
-                    });
-                }
+                previous_node = Some(node);
 
-                connected_nodes
+                (PfResultNode::Vec(config_node.location.vec3), edges)
             }
             PfNode::Portal { source_node } => {
                 let config_node = sign_config.nodes.get(source_node).unwrap();
                 let portal = config_node.portal.as_ref().unwrap();
-                let destination_node = sign_config.nodes.get(&portal.destination_node_name);
-
-                if destination_node.is_some() {
-                    vec![PfNode::Normal {
-                        node: config_node
-                            .portal
-                            .as_ref()
-                            .unwrap()
-                            .destination_node_name
-                            .clone(),
-                    }]
-                } else {
-                    vec![]
-                }
-            }
-        },
-        |node| match &node {
-            PfNode::Normal { node } => *node == ending_node,
-            _ => false,
-        },
-    );
-
-    path.map(|path| {
-        path.iter()
-            .map(|node| match node {
-                PfNode::Normal { node } => {
-                    let config_node = sign_config.nodes.get(node).unwrap();
-
-                    PfResultNode::Vec(config_node.location.vec3)
-                }
-                PfNode::Portal { source_node } => {
-                    let config_node = sign_config.nodes.get(source_node).unwrap();
-                    let portal = config_node.portal.as_ref().unwrap();
-                    let destination_node = sign_config
-                        .nodes
-                        .get(&portal.destination_node_name)
-                        .unwrap();
+                let destination_node = sign_config
+                    .nodes
+                    .get(&portal.destination_node_name)
+                    .unwrap();
+
+                previous_node = None;
 
+                (
                     PfResultNode::Portal {
                         vec: portal.vec3,
                         destination_dim: destination_node.location.dim,
-                    }
-                }
-            })
-            .collect::<Vec<PfResultNode>>()
-    })
-    .map(|mut path| {
-        path.insert(0, PfResultNode::Vec(starting_config_node.location.vec3));
-        path.push(PfResultNode::Vec(end_loc.vec3));
+                    },
+                    vec![PathEdge {
+                        source_node: source_node.clone(),
+                        destination_node: portal.destination_node_name.clone(),
+                    }],
+                )
+            }
+        });
+    }
 
-        path
-    })
-    .map(|mut path| {
-        let mut keep = vec![];
-        for (i, node) in path.iter().enumerate() {
-            let prev_node = path.get(i - 1);
-            let next_node = path.get(i + 1);
-
-            if let PfResultNode::Vec(curr_vec) = node {
-                if let Some(PfResultNode::Vec(prev_vec)) = prev_node {
-                    if let Some(PfResultNode::Vec(next_vec)) = next_node {
-                        keep.push(!is_exactly_in_between(*prev_vec, *curr_vec, *next_vec))
-                    } else {
-                        keep.push(true)
-                    }
-                } else {
-                    keep.push(true)
-                }
-            } else {
-                keep.push(true)
+    path.push((PfResultNode::Vec(end_loc.vec3), vec![]));
+
+    // Nodes in a straight line between their neighbours are skipped, the edges to them being
+    // covered on the way to the next node
+    let keep = (0..path.len())
+        .map(|i| {
+            let prev_node = i.checked_sub(1).map(|prev| &path[prev].0);
+            let next_node = path.get(i + 1).map(|(next_node, _edges)| next_node);
+
+            match (prev_node, &path[i].0, next_node) {
+                (
+                    Some(PfResultNode::Vec(prev_vec)),
+                    PfResultNode::Vec(curr_vec),
+                    Some(PfResultNode::Vec(next_vec)),
+                ) => !is_exactly_in_between(*prev_vec, *curr_vec, *next_vec),
+                _ => true,
             }
+        })
+        .collect::<Vec<_>>();
+
+    let mut found_path = FoundPath {
+        path: vec![],
+        edges: vec![],
+    };
+    let mut skipped_edges = vec![];
+
+    for ((node, mut edges), keep) in path.into_iter().zip(keep) {
+        skipped_edges.append(&mut edges);
+
+        if keep {
+            found_path.path.push(node);
+            found_path.edges.push(mem::take(&mut skipped_edges));
         }
+    }
 
-        let mut iter = keep.iter();
-        path.retain(|_| *iter.next().unwrap());
-        path
-    })
-    .ok_or(PathfindingError::NoPath)
+    Ok(found_path)
 }
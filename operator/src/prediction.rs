@@ -0,0 +1,179 @@
+use hashbrown::HashSet;
+use std::cmp::min;
+use uuid::Uuid;
+
+use crate::{
+    state::{
+        operations::{Operation, OperationKind, OperationPriority, OperationStatus},
+        State,
+    },
+    types::{Inventory, Item, Location},
+};
+
+fn hold_slot(state: &State, hold_id: Uuid) -> Option<(Location, u32)> {
+    state
+        .holds
+        .get(hold_id)
+        .map(|hold| (hold.location, hold.slot))
+}
+
+// Whether the agent reported the container's contents while carrying out the operation, in which
+// case they already show its effect
+pub fn observed_during(state: &State, location: &Location, op: &Operation) -> bool {
+    state
+        .operations
+        .was_reported(op.id, &state.inventories.canonical_location(location))
+}
+
+// Agents report container contents while they work, so only containers which the operation's
+// agent hasn't reported are eligible for a prediction
+fn predictable_inventory<'a>(
+    state: &'a mut State,
+    location: Location,
+    op: &Operation,
+) -> Option<&'a mut Inventory> {
+    if observed_during(state, &location, op) {
+        return None;
+    }
+
+    state.inventories.inventory_contents_at_mut(&location)
+}
+
+fn take_from_slot(
+    state: &mut State,
+    (location, slot): (Location, u32),
+    count: u32,
+    op: &Operation,
+) -> Option<Item> {
+    let inventory = predictable_inventory(state, location, op)?;
+    let slot_item = inventory.slots.get_mut(slot as usize)?;
+
+    let mut remaining = slot_item.take()?;
+    let mut moved = remaining.clone();
+
+    moved.count = min(count, remaining.count);
+    remaining.count -= moved.count;
+
+    if remaining.count > 0 {
+        *slot_item = Some(remaining);
+    }
+
+    inventory.predicted = true;
+
+    Some(moved)
+}
+
+fn put_into_slot(
+    state: &mut State,
+    (location, slot): (Location, u32),
+    item: Item,
+    op: &Operation,
+) -> bool {
+    let inventory = match predictable_inventory(state, location, op) {
+        Some(inventory) => inventory,
+        None => return false,
+    };
+
+    let slot_item = match inventory.slots.get_mut(slot as usize) {
+        Some(slot_item) => slot_item,
+        None => return false,
+    };
+
+    match slot_item {
+        None => *slot_item = Some(item),
+        Some(existing) if existing.stackable_hash == item.stackable_hash => {
+            existing.count += item.count
+        }
+        // Slot holds something else, leave it to the confirmation scan
+        Some(_) => return false,
+    }
+
+    inventory.predicted = true;
+
+    true
+}
+
+// Applies the expected effect of a completed operation to the inventory model, then queues
+// confirmation scans of any container that now holds predicted contents. Returns the slots whose
+// contents were predicted.
+pub fn apply_completed_operation(state: &mut State, op: &Operation) -> HashSet<(Location, u32)> {
+    let mut predicted_slots = HashSet::new();
+
+    match &op.kind {
+        OperationKind::MoveItems {
+            source_holds,
+            destination_holds,
+            counts,
+        } => {
+            for ((source_hold, destination_hold), count) in source_holds
+                .iter()
+                .zip(destination_holds.iter())
+                .zip(counts.iter())
+            {
+                let source = hold_slot(state, *source_hold);
+                let destination = hold_slot(state, *destination_hold);
+
+                if let (Some(source), Some(destination)) = (source, destination) {
+                    let moved = take_from_slot(state, source, (*count).max(0) as u32, op);
+
+                    if let Some(moved) = moved {
+                        predicted_slots.insert(source);
+
+                        if put_into_slot(state, destination, moved, op) {
+                            predicted_slots.insert(destination);
+                        }
+                    }
+                }
+            }
+        }
+        OperationKind::DropItems { source_holds, .. } => {
+            for source_hold in source_holds.iter() {
+                if let Some(source) = hold_slot(state, *source_hold) {
+                    if take_from_slot(state, source, u32::MAX, op).is_some() {
+                        predicted_slots.insert(source);
+                    }
+                }
+            }
+        }
+        _ => return predicted_slots,
+    }
+
+    let touched_locations = op
+        .holds()
+        .into_iter()
+        .flat_map(|hold_id| hold_slot(state, hold_id))
+        .map(|(location, _slot)| location)
+        .collect::<HashSet<Location>>();
+
+    let scans_outstanding = state
+        .operations
+        .iter(OperationStatus::Pending)
+        .chain(state.operations.iter(OperationStatus::InProgress))
+        .filter_map(|op| match op.kind {
+            OperationKind::ScanInventory { location, .. } => Some(location),
+            _ => None,
+        })
+        .collect::<HashSet<Location>>();
+
+    for location in touched_locations {
+        if scans_outstanding.contains(&location) {
+            continue;
+        }
+
+        let inventory = match state.inventories.inventory_contents_at(&location) {
+            Some(inventory) if inventory.predicted => inventory,
+            _ => continue,
+        };
+
+        let kind = OperationKind::ScanInventory {
+            location,
+            open_from: inventory.open_from,
+        };
+
+        state
+            .operations
+            .queue_operation(OperationPriority::Background, kind);
+    }
+
+    predicted_slots
+}
@@ -0,0 +1,133 @@
+use chrono::{Duration, Utc};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::service::Service;
+use crate::{
+    config::Config,
+    crafting::{available_count, item_name, queue_recipe, stack_size},
+    state::operations::{OperationKind, OperationPriority, OperationStatus},
+    state::State,
+    types::MC_DATA,
+};
+
+// Decompressed items are left alone this long after the operation completes, giving whoever asked
+// for them time to hold them
+const DECOMPRESSION_GRACE_MINUTES: i64 = 5;
+
+// Items being crafted, or crafted a moment ago, from their block form
+fn decompressing_items(state: &State) -> HashSet<u32> {
+    let recent = Utc::now() - Duration::minutes(DECOMPRESSION_GRACE_MINUTES);
+
+    state
+        .operations
+        .iter(OperationStatus::Pending)
+        .chain(state.operations.iter(OperationStatus::InProgress))
+        .chain(
+            state
+                .operations
+                .iter(OperationStatus::Complete)
+                .filter(|op| {
+                    op.finalized_at
+                        .is_some_and(|finalized_at| finalized_at > recent)
+                }),
+        )
+        .filter_map(|op| match op.kind {
+            OperationKind::Craft { item_id, .. } => Some(item_id),
+            _ => None,
+        })
+        .filter(|item_id| {
+            MC_DATA
+                .compressions
+                .iter()
+                .any(|compression| compression.item_id == *item_id)
+        })
+        .collect()
+}
+
+pub struct CompressorService {
+    outstanding_operations: Vec<Uuid>,
+    threshold: Option<u32>,
+    max_outstanding: usize,
+}
+
+impl Service for CompressorService {
+    fn get_name(&self) -> &'static str {
+        "compressor"
+    }
+
+    fn new(config: &Config) -> Self {
+        CompressorService {
+            outstanding_operations: vec![],
+            threshold: config.compression_threshold,
+            max_outstanding: config.compression_max_outstanding,
+        }
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        let threshold = match self.threshold {
+            Some(threshold) => threshold,
+            None => return,
+        };
+
+        self.outstanding_operations.retain(|op_id| {
+            let op = state.operations.get(*op_id);
+
+            if let Some(op) = op {
+                match op.status {
+                    OperationStatus::Complete | OperationStatus::Aborted => {
+                        for hold in op.holds() {
+                            state.holds.remove(hold);
+                        }
+
+                        false
+                    }
+                    OperationStatus::Pending | OperationStatus::InProgress => true,
+                }
+            } else {
+                false
+            }
+        });
+
+        let decompressing_items = decompressing_items(state);
+
+        for compression in MC_DATA.compressions.iter() {
+            if self.outstanding_operations.len() >= self.max_outstanding {
+                return;
+            }
+
+            // Compressing would undo a decompression for a delivery
+            if decompressing_items.contains(&compression.item_id) {
+                continue;
+            }
+
+            let surplus = available_count(state, compression.item_id).saturating_sub(threshold);
+            // One batch at a time, so compression never takes over storage
+            let crafts = (surplus / 9).min(stack_size(compression.item_id));
+
+            if crafts == 0 {
+                continue;
+            }
+
+            let mut operations = vec![];
+            let queued = queue_recipe(
+                state,
+                &compression.compress,
+                crafts,
+                OperationPriority::Background,
+                &mut vec![],
+                &mut operations,
+            );
+
+            if let Err((_crafts, err)) = queued {
+                debug!(
+                    "Could not compress all surplus {}: {}",
+                    item_name(compression.item_id),
+                    err
+                );
+            }
+
+            self.outstanding_operations.extend(operations);
+        }
+    }
+}
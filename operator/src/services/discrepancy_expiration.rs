@@ -0,0 +1,20 @@
+use crate::{config::Config, state::State};
+
+use super::service::Service;
+
+pub struct DiscrepancyExpirationService {}
+
+impl Service for DiscrepancyExpirationService {
+    fn get_name(&self) -> &'static str {
+        "discrepancy_expiration"
+    }
+
+    fn new(_config: &Config) -> Self {
+        Self {}
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        state.discrepancies.purge_old_discrepancies();
+        state.inventories.purge_old_touched();
+    }
+}
@@ -1,14 +1,12 @@
-use std::cmp::{max, min};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use uuid::Uuid;
 
 use crate::{
     config::Config,
     state::{
         operations::{OperationKind, OperationPriority, OperationStatus},
-        sign_config::StorageComplex,
         State,
     },
     types::{Location, Vec3},
@@ -16,13 +14,40 @@ use crate::{
 
 use super::service::Service;
 
+// How far back holds on a container count towards its access frequency
+const ACCESS_WINDOW_HOURS: i64 = 24;
+// Each recent access / discrepancy shortens the rescan interval by this share of the base interval
+const ACCESS_WEIGHT: f64 = 0.25;
+const DISCREPANCY_WEIGHT: f64 = 2.0;
+
 struct TrackedInventory {
     open_from: Vec3,
     current_scan_operation_id: Option<Uuid>,
+    recent_accesses: Vec<DateTime<Utc>>,
+}
+
+struct RescanPolicy {
+    interval: Duration,
+    min_interval: Duration,
+}
+
+impl RescanPolicy {
+    // Busy or unreliable containers are rescanned more often, down to the configured minimum
+    fn interval_for(&self, accesses: usize, discrepancies: usize) -> Duration {
+        let weight =
+            1.0 + ACCESS_WEIGHT * accesses as f64 + DISCREPANCY_WEIGHT * discrepancies as f64;
+        let interval = self.interval.num_seconds() as f64 / weight;
+
+        Duration::seconds(interval as i64).max(self.min_interval)
+    }
 }
 
 pub struct InventoryScannerService {
     tracked_inventories: HashMap<Location, TrackedInventory>,
+    // Holds present on the previous tick, so that each one is only counted as an access once
+    seen_holds: HashSet<Uuid>,
+    policy: RescanPolicy,
+    max_outstanding: usize,
 }
 
 impl Service for InventoryScannerService {
@@ -30,9 +55,15 @@ impl Service for InventoryScannerService {
         "inventory_scanner"
     }
 
-    fn new(_config: &Config) -> Self {
+    fn new(config: &Config) -> Self {
         InventoryScannerService {
             tracked_inventories: Default::default(),
+            seen_holds: Default::default(),
+            policy: RescanPolicy {
+                interval: Duration::minutes(config.rescan_interval_minutes),
+                min_interval: Duration::minutes(config.rescan_min_interval_minutes),
+            },
+            max_outstanding: config.rescan_max_outstanding,
         }
     }
 
@@ -40,85 +71,64 @@ impl Service for InventoryScannerService {
         let sign_config = state.sign_config.get_config();
 
         for (_name, complex) in sign_config.complexes.iter() {
-            match complex {
-                StorageComplex::FlatFloor {
-                    dimension,
-                    name: _name,
-                    y_level,
-                    bounds,
-                } => {
-                    let x1 = bounds.0.x;
-                    let x2 = bounds.1.x;
-                    let z1 = bounds.0.z;
-                    let z2 = bounds.1.z;
-
-                    for x in min(x1, x2)..=max(x1, x2) {
-                        for z in min(z1, z2)..=max(z1, z2) {
-                            let location = Location {
-                                vec3: Vec3 { x, y: *y_level, z },
-                                dim: *dimension,
-                            };
-                            if self.tracked_inventories.contains_key(&location) {
-                                continue;
-                            }
-
-                            self.tracked_inventories.insert(
-                                location,
-                                TrackedInventory {
-                                    open_from: Vec3 {
-                                        x,
-                                        y: (*y_level + 1),
-                                        z,
-                                    },
-                                    current_scan_operation_id: None,
-                                },
-                            );
-                        }
-                    }
-                }
-                StorageComplex::Tower {
-                    dimension,
-                    name: _name,
-                    origin,
-                    height,
-                } => {
-                    for y in (origin.y)..=(origin.y + (*height as i32) - 1) {
-                        for x in (origin.x - 4)..=(origin.x + 4) {
-                            for z in (origin.z - 4)..=(origin.z + 4) {
-                                if x == origin.x && z == origin.z {
-                                    continue;
-                                }
-
-                                let location = Location {
-                                    vec3: Vec3 { x, y, z },
-                                    dim: *dimension,
-                                };
-
-                                if self.tracked_inventories.contains_key(&location) {
-                                    continue;
-                                }
-
-                                self.tracked_inventories.insert(
-                                    location,
-                                    TrackedInventory {
-                                        open_from: Vec3 {
-                                            x: origin.x,
-                                            y,
-                                            z: origin.z,
-                                        },
-                                        current_scan_operation_id: None,
-                                    },
-                                );
-                            }
-                        }
-                    }
+            for (location, open_from) in complex.containers() {
+                if self.tracked_inventories.contains_key(&location) {
+                    continue;
                 }
+
+                self.tracked_inventories.insert(
+                    location,
+                    TrackedInventory {
+                        open_from,
+                        current_scan_operation_id: None,
+                        recent_accesses: vec![],
+                    },
+                );
+            }
+        }
+
+        let now = Utc::now();
+        let access_cutoff = now - Duration::hours(ACCESS_WINDOW_HOURS);
+
+        let current_holds = state
+            .holds
+            .iter()
+            .map(|hold| (hold.id, hold.location))
+            .collect::<Vec<_>>();
+
+        for (hold_id, location) in current_holds.iter() {
+            if self.seen_holds.contains(hold_id) {
+                continue;
+            }
+
+            if let Some(inventory) = self.tracked_inventories.get_mut(location) {
+                inventory.recent_accesses.push(now);
             }
         }
 
-        let rescan_time = Utc::now() - Duration::hours(2);
+        self.seen_holds = current_holds
+            .into_iter()
+            .map(|(hold_id, _location)| hold_id)
+            .collect();
+
+        let mut discrepancy_counts: HashMap<Location, usize> = HashMap::new();
+        for discrepancy in state.discrepancies.iter() {
+            *discrepancy_counts.entry(discrepancy.location).or_default() += 1;
+        }
+
+        // (urgency, location)
+        let mut rescan_candidates: Vec<(f64, Location)> = vec![];
 
         for (loc, inventory) in self.tracked_inventories.iter_mut() {
+            inventory
+                .recent_accesses
+                .retain(|accessed_at| *accessed_at > access_cutoff);
+
+            // Second half of a double chest, scanned through its canonical half
+            if state.inventories.canonical_location(loc) != *loc {
+                continue;
+            }
+
             if let Some(op_id) = inventory.current_scan_operation_id {
                 let op = state.operations.get(op_id);
 
@@ -133,28 +143,64 @@ impl Service for InventoryScannerService {
                 }
             }
 
-            let existing_inventory = state.inventories.inventory_contents_at(loc);
+            let existing_inventory = match state.inventories.inventory_contents_at(loc) {
+                Some(existing_inventory) => existing_inventory,
+                None => {
+                    // Never scanned, so we know nothing about it yet
+                    let kind = OperationKind::ScanInventory {
+                        location: *loc,
+                        open_from: inventory.open_from,
+                    };
+
+                    let op = state
+                        .operations
+                        .queue_operation(OperationPriority::SystemCritical, kind);
+
+                    inventory.current_scan_operation_id = Some(op.id);
 
-            let needs_rescan = match existing_inventory {
-                Some(inventory) => inventory.scanned_at < rescan_time,
-                None => true,
+                    continue;
+                }
             };
 
-            if !needs_rescan {
-                continue;
+            let age = now - existing_inventory.scanned_at;
+            let interval = self.policy.interval_for(
+                inventory.recent_accesses.len(),
+                discrepancy_counts.get(loc).copied().unwrap_or(0),
+            );
+
+            let urgency = age.num_seconds() as f64 / interval.num_seconds().max(1) as f64;
+
+            if urgency >= 1.0 {
+                rescan_candidates.push((urgency, *loc));
             }
+        }
 
-            let priority = match existing_inventory {
-                Some(_) => OperationPriority::Background,
-                None => OperationPriority::SystemCritical,
-            };
+        rescan_candidates.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        // Confirmation scans queued for completed operations count against the limit too
+        let outstanding_rescans = state
+            .operations
+            .iter(OperationStatus::Pending)
+            .chain(state.operations.iter(OperationStatus::InProgress))
+            .filter(|op| {
+                op.priority == OperationPriority::Background
+                    && matches!(op.kind, OperationKind::ScanInventory { .. })
+            })
+            .count();
+
+        let available_slots = self.max_outstanding.saturating_sub(outstanding_rescans);
+
+        for (_urgency, loc) in rescan_candidates.into_iter().take(available_slots) {
+            let inventory = self.tracked_inventories.get_mut(&loc).unwrap();
 
             let kind = OperationKind::ScanInventory {
-                location: *loc,
+                location: loc,
                 open_from: inventory.open_from,
             };
 
-            let op = state.operations.queue_operation(priority, kind);
+            let op = state
+                .operations
+                .queue_operation(OperationPriority::Background, kind);
 
             inventory.current_scan_operation_id = Some(op.id);
         }
@@ -1,11 +1,18 @@
 pub mod aborted_operation_recovery;
 pub mod agent_expiration;
 pub mod alert_expiration;
+pub mod compressor;
 pub mod defragger;
+pub mod discrepancy_expiration;
 pub mod hold_expiration;
 pub mod inventory_scanner;
 pub mod node_scanner;
 pub mod operation_expiration;
+pub mod path_penalty_decay;
 pub mod service;
 pub mod shulker_loader;
 pub mod shulker_unloader;
+pub mod sign_config_watch;
+pub mod smelter;
+pub mod stock_keeper;
+pub mod travel_time_refresh;
@@ -14,6 +14,7 @@ impl Service for OperationExpirationService {
     }
 
     fn tick(&mut self, state: &mut State) {
+        state.operations.abort_orphaned_operations();
         state.operations.purge_old_operations();
     }
 }
@@ -0,0 +1,22 @@
+use crate::{config::Config, state::State};
+
+use super::service::Service;
+
+pub struct PathPenaltyDecayService {}
+
+impl Service for PathPenaltyDecayService {
+    fn get_name(&self) -> &'static str {
+        "path_penalty_decay"
+    }
+
+    fn new(_config: &Config) -> Self {
+        Self {}
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        state.path_penalties.purge_expired_penalties();
+
+        let edge_penalties = state.path_penalties.current_costs();
+        state.sign_config.set_edge_penalties(edge_penalties);
+    }
+}
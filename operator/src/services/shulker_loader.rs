@@ -4,12 +4,14 @@ use super::service::Service;
 use crate::{
     config::Config,
     state::inventories::{InventoryListingOptions, ShulkerUnpacking},
-    state::operations::{OperationKind, OperationPriority, OperationStatus},
+    state::operations::{Operation, OperationKind, OperationPriority, OperationStatus},
     state::State,
+    types::ContainerKind,
 };
 
 pub struct ShulkerLoaderService {
-    outstanding_operation: Option<Uuid>,
+    outstanding_operations: Vec<Uuid>,
+    max_outstanding: usize,
 }
 
 impl Service for ShulkerLoaderService {
@@ -17,15 +19,16 @@ impl Service for ShulkerLoaderService {
         "shulker_loader"
     }
 
-    fn new(_config: &Config) -> Self {
+    fn new(config: &Config) -> Self {
         ShulkerLoaderService {
-            outstanding_operation: None,
+            outstanding_operations: vec![],
+            max_outstanding: config.shulker_max_outstanding,
         }
     }
 
     fn tick(&mut self, state: &mut State) {
-        if let Some(op_id) = self.outstanding_operation {
-            let op = state.operations.get(op_id);
+        self.outstanding_operations.retain(|op_id| {
+            let op = state.operations.get(*op_id);
 
             if let Some(op) = op {
                 match op.status {
@@ -34,30 +37,38 @@ impl Service for ShulkerLoaderService {
                             state.holds.remove(hold);
                         }
 
-                        self.outstanding_operation = None
+                        false
                     }
-                    OperationStatus::Pending | OperationStatus::InProgress => return,
+                    OperationStatus::Pending | OperationStatus::InProgress => true,
                 }
+            } else {
+                false
             }
+        });
+
+        if self.outstanding_operations.len() >= self.max_outstanding {
+            return;
         }
 
         let inv_listing = state.inventories.get_listing(InventoryListingOptions {
             shulker_unpacking: ShulkerUnpacking::None,
         });
 
+        let shulker_capacity = ContainerKind::ShulkerBox.slot_count() as usize;
+
         for item in inv_listing.iter() {
             if item.shulker_data.is_some() {
                 continue;
             }
 
-            if item.count < 2 * item.stack_size * 27 {
+            if item.count < 2 * item.stack_size * shulker_capacity as u32 {
                 continue;
             }
 
             let mut full_stacks = vec![];
 
             for (loc, slot, inv_item, open_from) in state.inventories.iter_slots() {
-                if full_stacks.len() == 27 {
+                if full_stacks.len() == shulker_capacity {
                     break;
                 }
                 if let Some(inv_item) = inv_item {
@@ -77,31 +88,7 @@ impl Service for ShulkerLoaderService {
                 }
             }
 
-            if full_stacks.len() == 27 {
-                let mut holds = vec![];
-
-                for (loc, slot, open_from) in full_stacks.into_iter() {
-                    holds.push(Some(
-                        state.holds.create(loc, slot as u32, open_from).unwrap().id,
-                    ));
-                }
-
-                let sign_config = state.sign_config.get_config();
-
-                let shulker_station_location = sign_config.nodes.iter().find_map(|(_, node)| {
-                    if node.shulker_station {
-                        return Some(node.location);
-                    }
-
-                    None
-                });
-
-                let shulker_station_location = if let Some(loc) = shulker_station_location {
-                    loc
-                } else {
-                    return;
-                };
-
+            if full_stacks.len() == shulker_capacity {
                 let empty_shulker =
                     state
                         .inventories
@@ -115,12 +102,45 @@ impl Service for ShulkerLoaderService {
                                 && state.holds.existing_hold(*loc, *slot as u32).is_none()
                         });
 
-                let empty_shulker_hold = if let Some((loc, slot, _, open_from)) = empty_shulker {
-                    state.holds.create(loc, slot as u32, open_from).unwrap().id
+                let (shulker_loc, shulker_slot, shulker_open_from) =
+                    if let Some((loc, slot, _, open_from)) = empty_shulker {
+                        (loc, slot, open_from)
+                    } else {
+                        return;
+                    };
+
+                let involved_locations = full_stacks
+                    .iter()
+                    .map(|(loc, _slot, _open_from)| *loc)
+                    .chain([shulker_loc])
+                    .collect::<Vec<_>>();
+
+                let shulker_station_location = if let Some(loc) =
+                    state.sign_config.get_config().best_shulker_station(
+                        &state
+                            .operations
+                            .station_occupancy(Operation::shulker_station_location),
+                        &involved_locations,
+                    ) {
+                    loc
                 } else {
                     return;
                 };
 
+                let mut holds = vec![];
+
+                for (loc, slot, open_from) in full_stacks.into_iter() {
+                    holds.push(Some(
+                        state.holds.create(loc, slot as u32, open_from).unwrap().id,
+                    ));
+                }
+
+                let empty_shulker_hold = state
+                    .holds
+                    .create(shulker_loc, shulker_slot as u32, shulker_open_from)
+                    .unwrap()
+                    .id;
+
                 let queued_op_id = state
                     .operations
                     .queue_operation(
@@ -133,7 +153,7 @@ impl Service for ShulkerLoaderService {
                     )
                     .id;
 
-                self.outstanding_operation = Some(queued_op_id);
+                self.outstanding_operations.push(queued_op_id);
 
                 return;
             }
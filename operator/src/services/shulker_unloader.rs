@@ -2,13 +2,15 @@ use super::service::Service;
 use crate::{
     config::Config,
     state::inventories::{InventoryListingOptions, ShulkerUnpacking},
-    state::operations::{OperationKind, OperationPriority, OperationStatus},
+    state::operations::{Operation, OperationKind, OperationPriority, OperationStatus},
     state::State,
+    types::ContainerKind,
 };
 use uuid::Uuid;
 
 pub struct ShulkerUnloaderService {
-    outstanding_operation: Option<Uuid>,
+    outstanding_operations: Vec<Uuid>,
+    max_outstanding: usize,
 }
 
 impl Service for ShulkerUnloaderService {
@@ -16,15 +18,16 @@ impl Service for ShulkerUnloaderService {
         "shulker_unloader"
     }
 
-    fn new(_config: &Config) -> Self {
+    fn new(config: &Config) -> Self {
         ShulkerUnloaderService {
-            outstanding_operation: None,
+            outstanding_operations: vec![],
+            max_outstanding: config.shulker_max_outstanding,
         }
     }
 
     fn tick(&mut self, state: &mut State) {
-        if let Some(op_id) = self.outstanding_operation {
-            let op = state.operations.get(op_id);
+        self.outstanding_operations.retain(|op_id| {
+            let op = state.operations.get(*op_id);
 
             if let Some(op) = op {
                 match op.status {
@@ -33,17 +36,25 @@ impl Service for ShulkerUnloaderService {
                             state.holds.remove(hold);
                         }
 
-                        self.outstanding_operation = None
+                        false
                     }
-                    OperationStatus::Pending | OperationStatus::InProgress => return,
+                    OperationStatus::Pending | OperationStatus::InProgress => true,
                 }
+            } else {
+                false
             }
+        });
+
+        if self.outstanding_operations.len() >= self.max_outstanding {
+            return;
         }
 
         let inv_listing = state.inventories.get_listing(InventoryListingOptions {
             shulker_unpacking: ShulkerUnpacking::None,
         });
 
+        let shulker_capacity = ContainerKind::ShulkerBox.slot_count() as usize;
+
         'shulker: for (loc, slot, item, open_from) in state.inventories.iter_slots() {
             if let Some(item) = item {
                 if let Some(shulker_data) = &item.shulker_data {
@@ -70,7 +81,7 @@ impl Service for ShulkerUnloaderService {
                         }
                     }
 
-                    let is_full = shulker_data.contained_items.len() == 27
+                    let is_full = shulker_data.contained_items.len() == shulker_capacity
                         && shulker_data
                             .contained_items
                             .iter()
@@ -80,7 +91,7 @@ impl Service for ShulkerUnloaderService {
                     if contains_one_type && is_full {
                         for item in inv_listing.iter() {
                             if &item.stackable_hash == first_item_hash {
-                                if item.count >= item.stack_size * 27 {
+                                if item.count >= item.stack_size * shulker_capacity as u32 {
                                     continue 'shulker;
                                 }
                             }
@@ -91,7 +102,7 @@ impl Service for ShulkerUnloaderService {
                         state.holds.create(loc, slot as u32, open_from).unwrap().id;
                     let mut destination_hold_ids = vec![];
 
-                    for _ in 0..27 {
+                    for _ in 0..shulker_capacity {
                         for (loc, slot, item, open_from) in state.inventories.iter_slots() {
                             if item.is_some()
                                 || state.holds.existing_hold(loc, slot as u32).is_some()
@@ -107,7 +118,7 @@ impl Service for ShulkerUnloaderService {
                         }
                     }
 
-                    if destination_hold_ids.len() < 27 {
+                    if destination_hold_ids.len() < shulker_capacity {
                         for hold in destination_hold_ids.iter() {
                             state.holds.remove(*hold);
                         }
@@ -115,20 +126,26 @@ impl Service for ShulkerUnloaderService {
                         return;
                     }
 
-                    let sign_config = state.sign_config.get_config();
-
-                    let shulker_station_location =
-                        sign_config.nodes.iter().find_map(|(_, node)| {
-                            if node.shulker_station {
-                                return Some(node.location);
-                            }
-
-                            None
-                        });
-
-                    let shulker_station_location = if let Some(loc) = shulker_station_location {
+                    let involved_locations = destination_hold_ids
+                        .iter()
+                        .map(|hold| state.holds.get(*hold).unwrap().location)
+                        .chain([loc])
+                        .collect::<Vec<_>>();
+
+                    let shulker_station_location = if let Some(loc) =
+                        state.sign_config.get_config().best_shulker_station(
+                            &state
+                                .operations
+                                .station_occupancy(Operation::shulker_station_location),
+                            &involved_locations,
+                        ) {
                         loc
                     } else {
+                        state.holds.remove(shulker_hold_id);
+                        for hold in destination_hold_ids.iter() {
+                            state.holds.remove(*hold);
+                        }
+
                         return;
                     };
 
@@ -144,7 +161,7 @@ impl Service for ShulkerUnloaderService {
                         )
                         .id;
 
-                    self.outstanding_operation = Some(queued_op_id);
+                    self.outstanding_operations.push(queued_op_id);
 
                     break;
                 }
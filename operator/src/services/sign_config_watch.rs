@@ -0,0 +1,72 @@
+use crate::{
+    config::Config,
+    state::{alerts::AlertSource, operations::OperationStatus, State},
+};
+
+use super::service::Service;
+
+pub struct SignConfigWatchService {
+    last_generation: u64,
+}
+
+impl Service for SignConfigWatchService {
+    fn get_name(&self) -> &'static str {
+        "sign_config_watch"
+    }
+
+    fn new(_config: &Config) -> Self {
+        Self { last_generation: 0 }
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        let last_generation = self.last_generation;
+        let mut descriptions = vec![];
+
+        for change in state
+            .sign_config
+            .history()
+            .iter()
+            .filter(|change| change.generation > last_generation)
+        {
+            self.last_generation = change.generation;
+
+            for (node_name, node_location) in change.removed_node_locations.iter() {
+                let referencing_operations = state
+                    .operations
+                    .iter(OperationStatus::Pending)
+                    .chain(state.operations.iter(OperationStatus::InProgress))
+                    .filter(|op| op.node_locations().contains(node_location))
+                    .count();
+
+                if referencing_operations > 0 {
+                    descriptions.push(format!(
+                        "Node {} was removed in sign config generation {} but is referenced by {} pending operation(s)",
+                        node_name, change.generation, referencing_operations
+                    ));
+                }
+            }
+        }
+
+        // Nodes were once all connected to each other, so bases set up before link signs existed
+        // have no paths until links are added
+        if self.last_generation != last_generation {
+            let sign_config = state.sign_config.get_config();
+
+            if sign_config.nodes.len() > 1
+                && sign_config
+                    .nodes
+                    .values()
+                    .all(|node| node.links.is_empty() && node.portal.is_none())
+            {
+                descriptions.push(format!(
+                    "None of the {} nodes are linked, so agents can't travel between them. Add an auto link sign in each dimension where agents can walk straight between any two nodes, or link signs between nodes",
+                    sign_config.nodes.len()
+                ));
+            }
+        }
+
+        for description in descriptions {
+            state.alerts.add_alert(AlertSource::Operator, description);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use chrono::{Duration, Utc};
+use std::mem;
+use uuid::Uuid;
+
+use super::service::Service;
+use crate::{
+    config::Config,
+    crafting::{ingredient_slots, item_name, stack_size},
+    smelting::{configured_fuels, fuel_slot, SMELT_SECONDS},
+    state::alerts::AlertSource,
+    state::operations::{OperationKind, OperationStatus},
+    state::smelting::{FurnaceLoad, SmeltJob},
+    state::State,
+    types::HoldRequestFilter,
+};
+
+// Furnaces only smelt while their chunk is loaded, so collecting from one that isn't done yet is
+// retried after this long
+const COLLECT_RETRY_SECONDS: i64 = 60;
+
+pub struct SmelterService {
+    fuels: Vec<u32>,
+}
+
+impl Service for SmelterService {
+    fn get_name(&self) -> &'static str {
+        "smelter"
+    }
+
+    fn new(config: &Config) -> Self {
+        SmelterService {
+            fuels: configured_fuels(&config.smelting_fuels),
+        }
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        let loads = mem::take(&mut state.smelting.loads);
+
+        for load in loads {
+            if let Some(load) = advance_load(state, load) {
+                state.smelting.loads.push(load);
+            }
+        }
+
+        let mut jobs = mem::take(&mut state.smelting.jobs);
+
+        for job in jobs.iter_mut() {
+            if !load_job(state, &self.fuels, job) {
+                break;
+            }
+        }
+
+        jobs.retain(|job| job.remaining > 0);
+        state.smelting.jobs = jobs;
+    }
+}
+
+fn release_op_holds(state: &mut State, op_id: Uuid) {
+    if let Some(op) = state.operations.get(op_id) {
+        for hold in op.holds() {
+            state.holds.remove(hold);
+        }
+    }
+}
+
+// Moves a furnace load on to its next step, returning it unless its furnace is free again
+fn advance_load(state: &mut State, mut load: FurnaceLoad) -> Option<FurnaceLoad> {
+    if let Some(collect_operation) = load.collect_operation {
+        match state.operations.get(collect_operation)?.status {
+            OperationStatus::Complete => {
+                release_op_holds(state, collect_operation);
+
+                return None;
+            }
+            // The furnace was still smelting or the agent didn't make it, either way the items are
+            // still in the furnace so collecting is tried again
+            OperationStatus::Aborted => {
+                release_op_holds(state, collect_operation);
+                load.collect_operation = None;
+                load.ready_at = Some(Utc::now() + Duration::seconds(COLLECT_RETRY_SECONDS));
+            }
+            OperationStatus::Pending | OperationStatus::InProgress => {}
+        }
+
+        return Some(load);
+    }
+
+    let ready_at = match load.ready_at {
+        Some(ready_at) => ready_at,
+        None => {
+            let smelt_operation = state.operations.get(load.smelt_operation)?;
+
+            match smelt_operation.status {
+                OperationStatus::Complete => {
+                    load.ready_at = Some(
+                        smelt_operation.finalized_at.unwrap_or_else(Utc::now)
+                            + Duration::seconds(load.count as i64 * SMELT_SECONDS),
+                    );
+                    release_op_holds(state, load.smelt_operation);
+                }
+                OperationStatus::Aborted => {
+                    warn!(
+                        "Smelting {} in furnace at {} was aborted, requeueing",
+                        item_name(load.input_id),
+                        load.furnace.vec3
+                    );
+                    requeue(state, &load);
+
+                    return None;
+                }
+                OperationStatus::Pending | OperationStatus::InProgress => {}
+            }
+
+            return Some(load);
+        }
+    };
+
+    if Utc::now() < ready_at {
+        return Some(load);
+    }
+
+    let mut destination_holds = vec![];
+
+    for _ in 0..load.count.div_ceil(stack_size(load.result_id)) {
+        match HoldRequestFilter::EmptySlot.attempt_match(state) {
+            Ok(holds) => destination_holds.extend(holds.into_iter().map(|hold| hold.id)),
+            // Waits in the furnace until there is space
+            Err(_) => {
+                for hold in destination_holds {
+                    state.holds.remove(hold);
+                }
+
+                return Some(load);
+            }
+        }
+    }
+
+    load.collect_operation = Some(
+        state
+            .operations
+            .queue_operation(
+                load.priority,
+                OperationKind::CollectSmelted {
+                    furnace_location: load.furnace.vec3,
+                    node_location: load.node_location,
+                    destination_holds,
+                    item_id: load.result_id,
+                },
+            )
+            .id,
+    );
+
+    Some(load)
+}
+
+fn requeue(state: &mut State, load: &FurnaceLoad) {
+    match state
+        .smelting
+        .jobs
+        .iter_mut()
+        .find(|job| job.id == load.job_id)
+    {
+        Some(job) => job.remaining += load.count,
+        None => state.smelting.jobs.push(SmeltJob {
+            id: load.job_id,
+            input_id: load.input_id,
+            result_id: load.result_id,
+            remaining: load.count,
+            priority: load.priority,
+            alerted: false,
+        }),
+    }
+}
+
+fn alert_once(state: &mut State, job: &mut SmeltJob, description: String) {
+    if !job.alerted {
+        job.alerted = true;
+        state.alerts.add_alert(AlertSource::Operator, description);
+    }
+}
+
+// Loads as much of the job as there are free furnaces for, spreading it evenly over the furnaces
+// of the least busy station. Returns false once every furnace is busy.
+fn load_job(state: &mut State, fuels: &[u32], job: &mut SmeltJob) -> bool {
+    let sign_config = state.sign_config.get_config();
+
+    while job.remaining > 0 {
+        let input_location = match ingredient_slots(state, job.input_id).first() {
+            Some(slot) => slot.location,
+            None => {
+                let loaded = state
+                    .smelting
+                    .loads
+                    .iter()
+                    .any(|load| load.job_id == job.id);
+
+                // Without anything loaded the job can never finish
+                if !loaded {
+                    alert_once(
+                        state,
+                        job,
+                        format!(
+                            "Ran out of {} to smelt with {} still to go",
+                            item_name(job.input_id),
+                            job.remaining
+                        ),
+                    );
+                    job.remaining = 0;
+                }
+
+                return true;
+            }
+        };
+
+        let busy_furnaces = state.smelting.busy_furnaces();
+        let station = match sign_config.best_furnace_station(&busy_furnaces, &[input_location]) {
+            Some(station) => station,
+            None => return false,
+        };
+
+        let free_furnaces = station.free_furnaces(&busy_furnaces).collect::<Vec<_>>();
+        let per_furnace = job
+            .remaining
+            .div_ceil(free_furnaces.len() as u32)
+            .min(stack_size(job.input_id));
+
+        let mut loaded_any = false;
+
+        for furnace in free_furnaces {
+            let input_slot = match ingredient_slots(state, job.input_id).into_iter().next() {
+                Some(input_slot) => input_slot,
+                None => break,
+            };
+
+            let count = per_furnace.min(job.remaining).min(input_slot.count);
+
+            // Loads no more than the fullest fuel slot burns for
+            let (fuel_slot, fuel_count, count) = match fuel_slot(state, fuels, job.input_id, count)
+            {
+                Some(fuel) => fuel,
+                None => {
+                    alert_once(
+                        state,
+                        job,
+                        format!(
+                            "No fuel in storage to smelt {} {}",
+                            count,
+                            item_name(job.input_id)
+                        ),
+                    );
+
+                    return true;
+                }
+            };
+
+            let input_hold = state
+                .holds
+                .create(input_slot.location, input_slot.slot, input_slot.open_from)
+                .unwrap()
+                .id;
+            let fuel_hold = state
+                .holds
+                .create(fuel_slot.location, fuel_slot.slot, fuel_slot.open_from)
+                .unwrap()
+                .id;
+
+            let smelt_operation = state
+                .operations
+                .queue_operation(
+                    job.priority,
+                    OperationKind::Smelt {
+                        furnace_location: furnace.vec3,
+                        node_location: station.location,
+                        input_hold,
+                        input_count: count,
+                        fuel_hold,
+                        fuel_count,
+                    },
+                )
+                .id;
+
+            state.smelting.loads.push(FurnaceLoad {
+                job_id: job.id,
+                furnace,
+                input_id: job.input_id,
+                node_location: station.location,
+                result_id: job.result_id,
+                count,
+                priority: job.priority,
+                smelt_operation,
+                ready_at: None,
+                collect_operation: None,
+            });
+
+            job.remaining -= count;
+            job.alerted = false;
+            loaded_any = true;
+
+            if job.remaining == 0 {
+                break;
+            }
+        }
+
+        if !loaded_any {
+            return true;
+        }
+    }
+
+    true
+}
@@ -0,0 +1,181 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use super::service::Service;
+use crate::{
+    config::Config,
+    crafting::{item_id_by_name, item_name},
+    crafting_planner::{plan_crafts, queue_plan},
+    state::alerts::AlertSource,
+    state::inventories::{InventoryListingOptions, ShulkerUnpacking},
+    state::operations::{OperationPriority, OperationStatus},
+    state::State,
+};
+
+struct KeptItem {
+    item_id: u32,
+    target: u32,
+    threshold: u32,
+    // Craft operations from the last restock, kept until all of them are finalized
+    outstanding_operations: Vec<Uuid>,
+    last_attempt: Option<DateTime<Utc>>,
+}
+
+pub struct StockKeeperService {
+    kept_items: Vec<KeptItem>,
+    interval: Duration,
+    max_outstanding: usize,
+    // Items whose last restock failed and has already been alerted on
+    alerted_items: HashSet<u32>,
+}
+
+impl Service for StockKeeperService {
+    fn get_name(&self) -> &'static str {
+        "stock_keeper"
+    }
+
+    fn new(config: &Config) -> Self {
+        let kept_items = config
+            .stock_targets
+            .iter()
+            .filter_map(|stock_target| match item_id_by_name(&stock_target.item) {
+                Ok(item_id) => Some(KeptItem {
+                    item_id,
+                    target: stock_target.target,
+                    threshold: stock_target.threshold.unwrap_or(stock_target.target),
+                    outstanding_operations: vec![],
+                    last_attempt: None,
+                }),
+                Err(err) => {
+                    error!("Ignoring stock target: {}", err);
+                    None
+                }
+            })
+            .collect();
+
+        StockKeeperService {
+            kept_items,
+            interval: Duration::minutes(config.stock_keeper_interval_minutes),
+            max_outstanding: config.stock_keeper_max_outstanding,
+            alerted_items: HashSet::new(),
+        }
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if self.kept_items.is_empty() {
+            return;
+        }
+
+        for kept_item in self.kept_items.iter_mut() {
+            let finalized = kept_item.outstanding_operations.iter().all(|op_id| {
+                state.operations.get(*op_id).is_none_or(|op| {
+                    matches!(
+                        op.status,
+                        OperationStatus::Complete | OperationStatus::Aborted
+                    )
+                })
+            });
+
+            // Later crafts in a chain take from the holds earlier ones fill
+            if finalized {
+                for op_id in kept_item.outstanding_operations.drain(..) {
+                    if let Some(op) = state.operations.get(op_id) {
+                        for hold in op.holds() {
+                            state.holds.remove(hold);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut outstanding = self
+            .kept_items
+            .iter()
+            .map(|kept_item| kept_item.outstanding_operations.len())
+            .sum::<usize>();
+
+        if outstanding >= self.max_outstanding {
+            return;
+        }
+
+        let mut stock = HashMap::new();
+        for item in state.inventories.get_listing(InventoryListingOptions {
+            shulker_unpacking: ShulkerUnpacking::UnnamedOnly,
+        }) {
+            *stock.entry(item.item_id).or_insert(0) += item.count;
+        }
+
+        let now = Utc::now();
+
+        for kept_item in self.kept_items.iter_mut() {
+            if outstanding >= self.max_outstanding {
+                break;
+            }
+
+            if !kept_item.outstanding_operations.is_empty()
+                || kept_item
+                    .last_attempt
+                    .is_some_and(|last_attempt| now - last_attempt < self.interval)
+            {
+                continue;
+            }
+
+            let in_stock = stock.get(&kept_item.item_id).copied().unwrap_or(0);
+            let shortfall = kept_item.target.saturating_sub(in_stock);
+            if in_stock >= kept_item.threshold || shortfall == 0 {
+                continue;
+            }
+
+            // Restock less at a time when the whole shortfall would take more operations than are
+            // left, only going over the limit when nothing else is outstanding
+            let mut count = shortfall;
+            let mut operation_count =
+                plan_crafts(state, kept_item.item_id, count).operation_count();
+
+            while outstanding + operation_count > self.max_outstanding && count > 1 {
+                count /= 2;
+                operation_count = plan_crafts(state, kept_item.item_id, count).operation_count();
+            }
+
+            if outstanding > 0 && outstanding + operation_count > self.max_outstanding {
+                continue;
+            }
+
+            kept_item.last_attempt = Some(now);
+
+            match queue_plan(
+                state,
+                kept_item.item_id,
+                count,
+                OperationPriority::Background,
+            ) {
+                Ok(queued) => {
+                    info!(
+                        "Restocking {} with {} Craft operation(s)",
+                        item_name(kept_item.item_id),
+                        queued.operations.len()
+                    );
+
+                    outstanding += queued.operations.len();
+                    kept_item.outstanding_operations = queued.operations;
+                    self.alerted_items.remove(&kept_item.item_id);
+                }
+                Err(err) => {
+                    if self.alerted_items.insert(kept_item.item_id) {
+                        state.alerts.add_alert(
+                            AlertSource::Operator,
+                            format!(
+                                "Unable to restock {} ({} of {} in stock): {}",
+                                item_name(kept_item.item_id),
+                                in_stock,
+                                kept_item.target,
+                                err
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{config::Config, state::State};
+
+use super::service::Service;
+
+// Learned costs change with every reported segment, so only rebuild the path graph periodically
+const REFRESH_INTERVAL_MINUTES: i64 = 5;
+
+pub struct TravelTimeRefreshService {
+    enabled: bool,
+    last_refresh: Option<DateTime<Utc>>,
+}
+
+impl Service for TravelTimeRefreshService {
+    fn get_name(&self) -> &'static str {
+        "travel_time_refresh"
+    }
+
+    fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.learned_travel_times,
+            last_refresh: None,
+        }
+    }
+
+    fn tick(&mut self, state: &mut State) {
+        if !self.enabled {
+            return;
+        }
+
+        let now = Utc::now();
+
+        if let Some(last_refresh) = self.last_refresh {
+            if now - last_refresh < Duration::minutes(REFRESH_INTERVAL_MINUTES) {
+                return;
+            }
+        }
+
+        self.last_refresh = Some(now);
+
+        let learned_edge_costs = state.travel_times.learned_edge_costs();
+        state.sign_config.set_learned_edge_costs(learned_edge_costs);
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::state::sign_config::Sign;
+
+// A layout declared outside the game, written in the same form as the signs agents scan
+#[derive(Serialize, Deserialize, Default)]
+pub struct DeclaredSignConfig {
+    pub signs: Vec<Sign>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SignConfigFormat {
+    #[default]
+    Json,
+    Toml,
+}
+
+impl SignConfigFormat {
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".toml") {
+            SignConfigFormat::Toml
+        } else {
+            SignConfigFormat::Json
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SignConfigFormat::Json => "application/json",
+            SignConfigFormat::Toml => "application/toml",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SignConfigFileError {
+    #[error("Unable to read sign config file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid JSON sign config: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid TOML sign config: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Unable to write TOML sign config: {0}")]
+    TomlSerialize(#[from] toml::ser::Error),
+}
+
+impl DeclaredSignConfig {
+    pub fn parse(input: &str, format: SignConfigFormat) -> Result<Self, SignConfigFileError> {
+        Ok(match format {
+            SignConfigFormat::Json => serde_json::from_str(input)?,
+            SignConfigFormat::Toml => toml::from_str(input)?,
+        })
+    }
+
+    pub fn read(path: &str) -> Result<Self, SignConfigFileError> {
+        let input = std::fs::read_to_string(path)?;
+
+        Self::parse(&input, SignConfigFormat::from_path(path))
+    }
+
+    pub fn render(&self, format: SignConfigFormat) -> Result<String, SignConfigFileError> {
+        Ok(match format {
+            SignConfigFormat::Json => serde_json::to_string_pretty(self)?,
+            SignConfigFormat::Toml => toml::to_string_pretty(self)?,
+        })
+    }
+}
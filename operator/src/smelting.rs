@@ -0,0 +1,221 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::cmp::Ordering;
+use thiserror::Error;
+
+use crate::{
+    crafting::{available_count, ingredient_slots, item_id_by_name, item_name, SourceSlot},
+    state::{
+        operations::{OperationPriority, OperationStatus},
+        smelting::{FurnaceLoad, SmeltJob},
+        State,
+    },
+    types::MC_DATA,
+};
+
+// Ticks a furnace takes to smelt one item
+const SMELT_TICKS: u32 = 200;
+// Seconds a furnace takes to smelt one item
+pub const SMELT_SECONDS: i64 = SMELT_TICKS as i64 / 20;
+
+#[derive(Error, Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum SmeltError {
+    #[error("No furnace recipe smelts {name}")]
+    NoRecipe { name: String },
+    #[error("Not enough {name} in storage, {needed} needed but {available} available")]
+    MissingInput {
+        name: String,
+        needed: u32,
+        available: u32,
+    },
+    #[error("No furnace station has been set up")]
+    NoFurnaceStation,
+}
+
+// Fuel items needed to smelt count items
+pub fn fuel_needed(fuel_id: u32, count: u32) -> Option<u32> {
+    let burn_ticks = MC_DATA.fuel_burn_ticks.get(&fuel_id)?;
+
+    Some((count * SMELT_TICKS).div_ceil(*burn_ticks))
+}
+
+// Fuel item ids from the configured names, or every known fuel but blaze rods which are usually
+// worth more than the burn time they give
+pub fn configured_fuels(fuel_names: &Option<Vec<String>>) -> Vec<u32> {
+    let fuel_names = match fuel_names {
+        Some(fuel_names) => fuel_names,
+        None => {
+            let blaze_rod = item_id_by_name("blaze_rod").ok();
+
+            let mut fuels = MC_DATA
+                .fuel_burn_ticks
+                .keys()
+                .copied()
+                .filter(|fuel_id| Some(*fuel_id) != blaze_rod)
+                .collect::<Vec<_>>();
+            fuels.sort();
+
+            return fuels;
+        }
+    };
+
+    fuel_names
+        .iter()
+        .filter_map(|name| match item_id_by_name(name) {
+            Ok(fuel_id) if MC_DATA.fuel_burn_ticks.contains_key(&fuel_id) => Some(fuel_id),
+            Ok(_) => {
+                error!("Ignoring smelting fuel {}: not a usable fuel", name);
+                None
+            }
+            Err(err) => {
+                error!("Ignoring smelting fuel: {}", err);
+                None
+            }
+        })
+        .collect()
+}
+
+// The unheld fuel slot that smelts the most of count items, wasting the least burn time between
+// slots that smelt as many. Gives how many fuel items to take from it and how many items they
+// smelt, which is less than count when no single slot has enough fuel.
+pub fn fuel_slot(
+    state: &State,
+    fuels: &[u32],
+    input_id: u32,
+    count: u32,
+) -> Option<(SourceSlot, u32, u32)> {
+    let mut best: Option<(SourceSlot, u32, u32, u32)> = None;
+
+    for fuel_id in fuels.iter() {
+        let burn_ticks = MC_DATA.fuel_burn_ticks.get(fuel_id)?;
+
+        // Fuel that is also the input would be smelted away
+        if *fuel_id == input_id {
+            continue;
+        }
+
+        let slot = match ingredient_slots(state, *fuel_id).into_iter().next() {
+            Some(slot) => slot,
+            None => continue,
+        };
+
+        let smelted = count.min(slot.count * burn_ticks / SMELT_TICKS);
+        if smelted == 0 {
+            continue;
+        }
+
+        let needed = fuel_needed(*fuel_id, smelted).unwrap();
+        let wasted = needed * burn_ticks - smelted * SMELT_TICKS;
+
+        let better = best
+            .as_ref()
+            .is_none_or(|(_slot, _needed, best_smelted, best_wasted)| {
+                smelted.cmp(best_smelted).then(best_wasted.cmp(&wasted)) == Ordering::Greater
+            });
+
+        if better {
+            best = Some((slot, needed, smelted, wasted));
+        }
+    }
+
+    best.map(|(slot, needed, smelted, _wasted)| (slot, needed, smelted))
+}
+
+// Queues count of the item to be smelted, spread over free furnaces as they become available
+pub fn queue_smelting(
+    state: &mut State,
+    input_id: u32,
+    count: u32,
+    priority: OperationPriority,
+) -> Result<SmeltJob, SmeltError> {
+    let result_id =
+        *MC_DATA
+            .smelting_results
+            .get(&input_id)
+            .ok_or_else(|| SmeltError::NoRecipe {
+                name: item_name(input_id),
+            })?;
+
+    let has_furnace_station = state
+        .sign_config
+        .get_config()
+        .nodes
+        .values()
+        .any(|node| !node.furnaces.is_empty());
+
+    if !has_furnace_station {
+        return Err(SmeltError::NoFurnaceStation);
+    }
+
+    let available = available_count(state, input_id);
+    if available < count {
+        return Err(SmeltError::MissingInput {
+            name: item_name(input_id),
+            needed: count,
+            available,
+        });
+    }
+
+    Ok(state
+        .smelting
+        .queue_job(input_id, result_id, count, priority)
+        .clone())
+}
+
+fn learned_seconds(state: &State, kind_name: &str) -> Duration {
+    Duration::milliseconds(
+        (state
+            .travel_times
+            .operation_seconds(kind_name)
+            .unwrap_or(0.0)
+            * 1000.0) as i64,
+    )
+}
+
+// When a load's items are expected to be smelted, from its Smelt operation's prediction until
+// that operation completes
+pub fn predicted_ready_at(state: &State, load: &FurnaceLoad) -> Option<DateTime<Utc>> {
+    if let Some(ready_at) = load.ready_at {
+        return Some(ready_at);
+    }
+
+    let smelt_operation = state.operations.get(load.smelt_operation)?;
+    let loaded_at = match smelt_operation.status {
+        OperationStatus::Pending => Utc::now() + learned_seconds(state, "Smelt"),
+        OperationStatus::InProgress => smelt_operation.predicted_completion_at?,
+        OperationStatus::Complete | OperationStatus::Aborted => return None,
+    };
+
+    Some(loaded_at + Duration::seconds(load.count as i64 * SMELT_SECONDS))
+}
+
+// When a job is expected to be fully collected: after its loaded items are ready, the items still
+// waiting take turns over every furnace
+pub fn predicted_completion_at(state: &State, job: &SmeltJob) -> Option<DateTime<Utc>> {
+    let furnace_count = state
+        .sign_config
+        .get_config()
+        .nodes
+        .values()
+        .map(|node| node.furnaces.len() as u32)
+        .sum::<u32>();
+
+    if furnace_count == 0 {
+        return None;
+    }
+
+    let mut last_ready_at = Utc::now();
+
+    for load in state.smelting.loads.iter() {
+        if load.job_id == job.id {
+            last_ready_at = last_ready_at.max(predicted_ready_at(state, load)?);
+        }
+    }
+
+    Some(
+        last_ready_at
+            + Duration::seconds(job.remaining.div_ceil(furnace_count) as i64 * SMELT_SECONDS)
+            + learned_seconds(state, "CollectSmelted"),
+    )
+}
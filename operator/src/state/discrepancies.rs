@@ -0,0 +1,204 @@
+use hashbrown::HashSet;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use crate::types::{Inventory, Item, Location};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Discrepancy {
+    pub location: Location,
+    pub slot: u32,
+    pub previous: Option<Item>,
+    pub current: Option<Item>,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl Discrepancy {
+    fn describe_slot(&self) -> String {
+        let describe_item = |item: &Option<Item>| match item {
+            Some(item) => item.to_string(),
+            None => String::from("empty"),
+        };
+
+        format!(
+            "slot {}: {} -> {}",
+            self.slot,
+            describe_item(&self.previous),
+            describe_item(&self.current)
+        )
+    }
+}
+
+// One description for every change found in a scan of the inventory at location
+pub fn describe_changes(location: Location, discrepancies: &[Discrepancy]) -> String {
+    format!(
+        "{} unexpected change(s) in inventory at {} in {:?}: {}",
+        discrepancies.len(),
+        location.vec3,
+        location.dim,
+        discrepancies
+            .iter()
+            .map(|discrepancy| discrepancy.describe_slot())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[derive(Default)]
+pub struct DiscrepancyState {
+    discrepancies: Vec<Discrepancy>,
+}
+
+impl DiscrepancyState {
+    pub fn record(&mut self, discrepancy: Discrepancy) -> &Discrepancy {
+        self.discrepancies.push(discrepancy);
+
+        self.discrepancies.last().unwrap()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Discrepancy> {
+        self.discrepancies.iter()
+    }
+
+    pub fn purge_old_discrepancies(&mut self) {
+        self.discrepancies
+            .retain(|discrepancy| discrepancy.detected_at > (Utc::now() - Duration::hours(24)));
+    }
+}
+
+// Compares two scans of the same inventory, ignoring slots whose change is explained by an
+// operation in progress or one whose effect on them is unknown
+pub fn find_unexplained_changes(
+    location: Location,
+    previous: &Inventory,
+    current: &Inventory,
+    explained_slots: &HashSet<u32>,
+) -> Vec<Discrepancy> {
+    let mut discrepancies = vec![];
+    let slot_count_max = previous.slots.len().max(current.slots.len());
+
+    for slot in 0..slot_count_max {
+        if explained_slots.contains(&(slot as u32)) {
+            continue;
+        }
+
+        let previous_item = previous.slots.get(slot).cloned().flatten();
+        let current_item = current.slots.get(slot).cloned().flatten();
+
+        let unchanged = match (&previous_item, &current_item) {
+            (None, None) => true,
+            (Some(a), Some(b)) => a.stackable_hash == b.stackable_hash && a.count == b.count,
+            _ => false,
+        };
+
+        if unchanged {
+            continue;
+        }
+
+        discrepancies.push(Discrepancy {
+            location,
+            slot: slot as u32,
+            previous: previous_item,
+            current: current_item,
+            detected_at: current.scanned_at,
+        });
+    }
+
+    discrepancies
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::types::{ContainerKind, Dimension, Vec3};
+
+    const LOCATION: Location = Location {
+        vec3: Vec3 { x: 1, y: 64, z: 2 },
+        dim: Dimension::Overworld,
+    };
+
+    fn item(item_id: u32, count: u32) -> Option<Item> {
+        Some(Item {
+            item_id,
+            count,
+            metadata: 0,
+            nbt: Arc::new(Value::Null),
+            stack_size: 64,
+            stackable_hash: item_id as u64,
+            shulker_data: None,
+        })
+    }
+
+    fn inventory(slots: Vec<Option<Item>>) -> Inventory {
+        Inventory {
+            kind: ContainerKind::Chest,
+            slots,
+            scanned_at: Utc::now(),
+            open_from: Vec3 { x: 1, y: 65, z: 2 },
+            predicted: false,
+        }
+    }
+
+    fn changed_slots(discrepancies: &[Discrepancy]) -> Vec<u32> {
+        discrepancies
+            .iter()
+            .map(|discrepancy| discrepancy.slot)
+            .collect()
+    }
+
+    #[test]
+    fn finds_nothing_in_identical_scans() {
+        let slots = vec![item(1, 64), None, item(2, 3)];
+
+        let discrepancies = find_unexplained_changes(
+            LOCATION,
+            &inventory(slots.clone()),
+            &inventory(slots),
+            &HashSet::new(),
+        );
+
+        assert!(discrepancies.is_empty());
+    }
+
+    #[test]
+    fn finds_changed_counts_items_and_emptied_slots() {
+        let previous = inventory(vec![item(1, 64), item(2, 3), item(3, 1), None]);
+        let current = inventory(vec![item(1, 60), item(4, 3), None, item(5, 8)]);
+
+        let discrepancies =
+            find_unexplained_changes(LOCATION, &previous, &current, &HashSet::new());
+
+        assert_eq!(changed_slots(&discrepancies), vec![0, 1, 2, 3]);
+        assert_eq!(discrepancies[2].previous, item(3, 1));
+        assert_eq!(discrepancies[2].current, None);
+        assert_eq!(discrepancies[2].location, LOCATION);
+    }
+
+    #[test]
+    fn ignores_explained_slots() {
+        let previous = inventory(vec![item(1, 64), item(2, 3)]);
+        let current = inventory(vec![item(1, 32), None]);
+
+        let discrepancies =
+            find_unexplained_changes(LOCATION, &previous, &current, &HashSet::from([0]));
+
+        assert_eq!(changed_slots(&discrepancies), vec![1]);
+    }
+
+    #[test]
+    fn compares_slots_missing_from_either_scan_as_empty() {
+        let previous = inventory(vec![None, item(1, 1)]);
+        let current = inventory(vec![None, item(1, 1), None, item(2, 5)]);
+
+        let discrepancies =
+            find_unexplained_changes(LOCATION, &previous, &current, &HashSet::new());
+
+        assert_eq!(changed_slots(&discrepancies), vec![3]);
+        assert_eq!(discrepancies[0].previous, None);
+    }
+}
@@ -1,26 +1,97 @@
-use hashbrown::{hash_map::Iter, HashMap};
+use chrono::{DateTime, Duration, Utc};
+use hashbrown::{hash_map::Iter, HashMap, HashSet};
 
-use crate::types::{Inventory, Item, Location, Vec3};
+use crate::types::{ContainerKind, Inventory, Item, Location, Vec3};
 
 pub struct InventoryState {
     inventory_map: HashMap<Location, Inventory>,
+    // Slots modified in an unknown way by finalized operations since the inventory was last
+    // scanned, and when they were last touched
+    touched_slots: HashMap<Location, (DateTime<Utc>, HashSet<u32>)>,
+    // Non-canonical half of a double chest -> location the inventory is stored under
+    aliases: HashMap<Location, Location>,
 }
 
 impl Default for InventoryState {
     fn default() -> Self {
         InventoryState {
             inventory_map: Default::default(),
+            touched_slots: Default::default(),
+            aliases: Default::default(),
         }
     }
 }
 
 impl InventoryState {
     pub fn set_inventory_at(&mut self, location: Location, inventory: Inventory) {
-        self.inventory_map.insert(location, inventory);
+        let canonical = inventory.kind.canonical_location(location);
+
+        // Forget any previous pairing, the container may have been split or re-paired
+        self.aliases
+            .retain(|alias, target| *alias != canonical && *target != canonical);
+
+        if let ContainerKind::DoubleChest { other_half } = inventory.kind {
+            let halves = [
+                location,
+                Location {
+                    vec3: other_half,
+                    dim: location.dim,
+                },
+            ];
+
+            for half in halves {
+                if half != canonical {
+                    self.inventory_map.remove(&half);
+                    self.aliases.insert(half, canonical);
+                }
+            }
+        }
+
+        self.inventory_map.insert(canonical, inventory);
+    }
+
+    pub fn canonical_location(&self, location: &Location) -> Location {
+        self.aliases.get(location).copied().unwrap_or(*location)
+    }
+
+    pub fn mark_touched(&mut self, location: Location, slot: u32) {
+        let location = self.canonical_location(&location);
+
+        let (touched_at, slots) = self
+            .touched_slots
+            .entry(location)
+            .or_insert_with(|| (Utc::now(), HashSet::new()));
+
+        *touched_at = Utc::now();
+        slots.insert(slot);
+    }
+
+    pub fn take_touched(&mut self, location: &Location) -> HashSet<u32> {
+        let location = self.canonical_location(location);
+
+        self.touched_slots
+            .remove(&location)
+            .map(|(_touched_at, slots)| slots)
+            .unwrap_or_default()
+    }
+
+    // Forgets touched slots of containers that haven't been scanned in a day, such as ones no
+    // longer in storage
+    pub fn purge_old_touched(&mut self) {
+        self.touched_slots
+            .retain(|_location, (touched_at, _slots)| {
+                *touched_at > Utc::now() - Duration::hours(24)
+            });
     }
 
     pub fn inventory_contents_at(&self, location: &Location) -> Option<&Inventory> {
-        self.inventory_map.get(location)
+        self.inventory_map.get(&self.canonical_location(location))
+    }
+
+    pub fn inventory_contents_at_mut(&mut self, location: &Location) -> Option<&mut Inventory> {
+        let location = self.canonical_location(location);
+
+        self.inventory_map.get_mut(&location)
     }
 
     pub fn iter_inventories(&self) -> Iter<Location, Inventory> {
@@ -1,25 +1,36 @@
 use self::{
-    agents::AgentState, alerts::AlertState, holds::HoldState, inventories::InventoryState,
-    metrics::MetricsState, operations::OperationState, sign_config::SignConfigState,
+    agents::AgentState, alerts::AlertState, discrepancies::DiscrepancyState, holds::HoldState,
+    inventories::InventoryState, metrics::MetricsState, operations::OperationState,
+    path_penalties::PathPenaltyState, sign_config::SignConfigState, smelting::SmeltingState,
+    travel_times::TravelTimeState,
 };
 use actix_web::web;
 use std::sync::Mutex;
 
 pub mod agents;
 pub mod alerts;
+pub mod discrepancies;
 pub mod holds;
 pub mod inventories;
 pub mod metrics;
 pub mod operations;
+pub mod path_penalties;
 pub mod sign_config;
+pub mod sign_config_history;
+pub mod smelting;
+pub mod travel_times;
 
 pub struct State {
     pub inventories: InventoryState,
     pub operations: OperationState,
     pub agents: AgentState,
     pub alerts: AlertState,
+    pub discrepancies: DiscrepancyState,
     pub holds: HoldState,
+    pub path_penalties: PathPenaltyState,
     pub sign_config: SignConfigState,
+    pub smelting: SmeltingState,
+    pub travel_times: TravelTimeState,
     pub metrics: MetricsState,
 }
 
@@ -30,8 +41,12 @@ impl Default for State {
             operations: Default::default(),
             agents: Default::default(),
             alerts: Default::default(),
+            discrepancies: Default::default(),
             holds: Default::default(),
+            path_penalties: Default::default(),
             sign_config: Default::default(),
+            smelting: Default::default(),
+            travel_times: Default::default(),
             metrics: Default::default(),
         }
     }
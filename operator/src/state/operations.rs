@@ -1,8 +1,12 @@
-use crate::types::{Location, Vec3};
+use crate::{
+    pathfinding::TravelEstimator,
+    state::travel_times::{TravelTimeState, DEFAULT_BLOCKS_PER_SECOND},
+    types::{Location, Vec3},
+};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -28,7 +32,16 @@ pub struct Operation {
     pub priority: OperationPriority,
     pub status: OperationStatus,
     pub kind: OperationKind,
+    pub started_at: Option<DateTime<Utc>>,
     pub finalized_at: Option<DateTime<Utc>>,
+    // Based on learned travel and operation times, once there are enough samples
+    pub predicted_completion_at: Option<DateTime<Utc>>,
+    // Expected walk from where the agent took the operation, so it can be left out of the
+    // learned operation time
+    #[serde(skip)]
+    pub travel_seconds: Option<f64>,
+    // Operations that must complete before this one can be taken
+    pub depends_on: Vec<Uuid>,
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
@@ -60,8 +73,27 @@ pub enum OperationKind {
     Craft {
         crafting_table_location: Vec3,
         node_location: Location,
+        // Row by row over the 3x3 grid, the same hold may feed several cells
         recipe_source_holds: Vec<Option<Uuid>>,
         destination_holds: Vec<Uuid>,
+        item_id: u32,
+        // Times the recipe is crafted, each cell takes this many items from its hold
+        crafts: u32,
+    },
+    Smelt {
+        furnace_location: Vec3,
+        node_location: Location,
+        input_hold: Uuid,
+        input_count: u32,
+        fuel_hold: Uuid,
+        fuel_count: u32,
+    },
+    // Takes what a furnace has smelted once a Smelt operation's items are done
+    CollectSmelted {
+        furnace_location: Vec3,
+        node_location: Location,
+        destination_holds: Vec<Uuid>,
+        item_id: u32,
     },
     LoadShulker {
         shulker_station_location: Location,
@@ -78,6 +110,8 @@ pub enum OperationKind {
 pub struct OperationState {
     operations: HashMap<Uuid, Operation>,
     pending_operation_ids: Vec<(Uuid, OperationPriority)>,
+    // Containers agents reported the contents of while carrying out each operation
+    reported_locations: HashMap<Uuid, HashSet<Location>>,
 }
 
 impl Default for OperationState {
@@ -85,6 +119,7 @@ impl Default for OperationState {
         OperationState {
             operations: Default::default(),
             pending_operation_ids: Default::default(),
+            reported_locations: Default::default(),
         }
     }
 }
@@ -100,6 +135,15 @@ impl OperationState {
         &mut self,
         priority: OperationPriority,
         kind: OperationKind,
+    ) -> &Operation {
+        self.queue_operation_after(priority, kind, vec![])
+    }
+
+    pub fn queue_operation_after(
+        &mut self,
+        priority: OperationPriority,
+        kind: OperationKind,
+        depends_on: Vec<Uuid>,
     ) -> &Operation {
         let id = Uuid::new_v4();
         self.operations.insert(
@@ -109,7 +153,11 @@ impl OperationState {
                 priority,
                 status: OperationStatus::Pending,
                 kind,
+                started_at: None,
                 finalized_at: None,
+                predicted_completion_at: None,
+                travel_seconds: None,
+                depends_on,
             },
         );
 
@@ -122,13 +170,16 @@ impl OperationState {
         &mut self,
         starting_loc: Location,
         has_clear_inventory: bool,
+        estimator: &TravelEstimator,
+        travel_times: &TravelTimeState,
+        use_learned_times: bool,
     ) -> Option<&Operation> {
         let shulker_stations_in_use = self
             .iter(OperationStatus::InProgress)
             .flat_map(|op| op.shulker_station_location())
             .collect::<Vec<Location>>();
 
-        let mut leading_operation: Option<(usize, Uuid, &Operation, i32)> = None;
+        let mut leading_operation: Option<(usize, Uuid, &Operation, u32)> = None;
 
         for (idx, (op_id, _priority)) in self.pending_operation_ids.iter().enumerate() {
             let op = self.operations.get(&op_id).unwrap();
@@ -137,6 +188,10 @@ impl OperationState {
                 continue;
             }
 
+            if self.is_waiting(op) {
+                continue;
+            }
+
             let shulker_station_available = op
                 .shulker_station_location()
                 .as_ref()
@@ -147,10 +202,19 @@ impl OperationState {
                 continue;
             }
 
-            let est_dist_cost = op
-                .starting_location()
-                .map(|loc| loc.distance_heuristic(&starting_loc))
-                .unwrap_or(0);
+            // Expected milliseconds, walking at the default speed until agent speed has been
+            // learned
+            let est_seconds = use_learned_times
+                .then(|| travel_times.expected_seconds(estimator, starting_loc, op))
+                .flatten()
+                .unwrap_or_else(|| {
+                    op.starting_location()
+                        .map(|loc| {
+                            estimator.estimate(starting_loc, loc) as f64 / DEFAULT_BLOCKS_PER_SECOND
+                        })
+                        .unwrap_or(0.0)
+                });
+            let est_dist_cost = (est_seconds * 1000.0) as u32;
 
             if let Some((_other_idx, _other_op_id, other_op, other_est_dist_cost)) =
                 leading_operation
@@ -169,7 +233,14 @@ impl OperationState {
         }
 
         if let Some((idx, op_id, _op, _priority)) = leading_operation {
-            self.operations.get_mut(&op_id).unwrap().status = OperationStatus::InProgress;
+            let op = self.operations.get_mut(&op_id).unwrap();
+            let now = Utc::now();
+            op.status = OperationStatus::InProgress;
+            op.started_at = Some(now);
+            op.travel_seconds = travel_times.travel_seconds(estimator, starting_loc, op);
+            op.predicted_completion_at = travel_times
+                .expected_seconds(estimator, starting_loc, op)
+                .map(|seconds| now + Duration::milliseconds((seconds * 1000.0) as i64));
             self.pending_operation_ids.remove(idx);
 
             return self.operations.get(&op_id);
@@ -178,6 +249,25 @@ impl OperationState {
         }
     }
 
+    // Operations queued or running at each station, as located by station_location
+    pub fn station_occupancy(
+        &self,
+        station_location: fn(&Operation) -> Option<Location>,
+    ) -> HashMap<Location, usize> {
+        let mut occupancy = HashMap::new();
+
+        for op in self
+            .iter(OperationStatus::Pending)
+            .chain(self.iter(OperationStatus::InProgress))
+        {
+            if let Some(station) = station_location(op) {
+                *occupancy.entry(station).or_insert(0) += 1;
+            }
+        }
+
+        occupancy
+    }
+
     pub fn set_operation_status(
         &mut self,
         operation_id: Uuid,
@@ -207,10 +297,76 @@ impl OperationState {
             .map(|(_id, op)| op)
     }
 
+    pub fn record_report(&mut self, id: Uuid, location: Location) {
+        self.reported_locations
+            .entry(id)
+            .or_default()
+            .insert(location);
+    }
+
+    pub fn was_reported(&self, id: Uuid, location: &Location) -> bool {
+        self.reported_locations
+            .get(&id)
+            .is_some_and(|locations| locations.contains(location))
+    }
+
     pub fn get(&self, id: Uuid) -> Option<&Operation> {
         self.operations.get(&id)
     }
 
+    // Dependencies that have already been purged finished long ago
+    fn is_waiting(&self, op: &Operation) -> bool {
+        op.depends_on.iter().any(|dependency| {
+            self.operations.get(dependency).is_some_and(|dependency| {
+                matches!(
+                    dependency.status,
+                    OperationStatus::Pending | OperationStatus::InProgress
+                )
+            })
+        })
+    }
+
+    pub fn abort_pending_operation(&mut self, operation_id: Uuid) {
+        self.pending_operation_ids
+            .retain(|(op_id, _priority)| *op_id != operation_id);
+
+        let _ = self.set_operation_status(operation_id, OperationStatus::Aborted);
+    }
+
+    // Takes back an operation that was only just queued, before any agent could have started it
+    pub fn remove_pending_operation(&mut self, operation_id: Uuid) -> Option<Operation> {
+        self.pending_operation_ids
+            .retain(|(op_id, _priority)| *op_id != operation_id);
+        self.reported_locations.remove(&operation_id);
+
+        self.operations.remove(&operation_id)
+    }
+
+    // Pending operations that depend on an aborted operation can never run, so are aborted too
+    pub fn abort_orphaned_operations(&mut self) {
+        loop {
+            let orphaned = self
+                .iter(OperationStatus::Pending)
+                .filter(|op| {
+                    op.depends_on.iter().any(|dependency| {
+                        self.operations
+                            .get(dependency)
+                            .is_some_and(|dependency| dependency.status == OperationStatus::Aborted)
+                    })
+                })
+                .map(|op| op.id)
+                .collect::<Vec<_>>();
+
+            if orphaned.is_empty() {
+                break;
+            }
+
+            for op_id in orphaned {
+                self.abort_pending_operation(op_id);
+            }
+        }
+    }
+
     pub fn purge_old_operations(&mut self) {
         let retain_time = Utc::now() - Duration::minutes(15);
         self.operations.retain(|_, op| match op.status {
@@ -223,6 +379,27 @@ impl OperationState {
                 }
             }
         });
+
+        let operations = &self.operations;
+        self.reported_locations
+            .retain(|op_id, _locations| operations.contains_key(op_id));
+    }
+}
+
+impl OperationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OperationKind::ScanInventory { .. } => "ScanInventory",
+            OperationKind::ScanSigns { .. } => "ScanSigns",
+            OperationKind::MoveItems { .. } => "MoveItems",
+            OperationKind::DropItems { .. } => "DropItems",
+            OperationKind::ImportInventory { .. } => "ImportInventory",
+            OperationKind::Craft { .. } => "Craft",
+            OperationKind::Smelt { .. } => "Smelt",
+            OperationKind::CollectSmelted { .. } => "CollectSmelted",
+            OperationKind::LoadShulker { .. } => "LoadShulker",
+            OperationKind::UnloadShulker { .. } => "UnloadShulker",
+        }
     }
 }
 
@@ -242,7 +419,15 @@ impl Operation {
             OperationKind::DropItems { source_holds, .. } => source_holds.clone(),
             OperationKind::ImportInventory {
                 destination_holds, ..
+            }
+            | OperationKind::CollectSmelted {
+                destination_holds, ..
             } => destination_holds.clone(),
+            OperationKind::Smelt {
+                input_hold,
+                fuel_hold,
+                ..
+            } => vec![*input_hold, *fuel_hold],
             OperationKind::Craft {
                 recipe_source_holds,
                 destination_holds,
@@ -303,6 +488,26 @@ impl Operation {
         }
     }
 
+    // Node locations the agent has to travel to to carry out the operation
+    pub fn node_locations(&self) -> Vec<Location> {
+        match &self.kind {
+            OperationKind::ScanSigns { location, .. } => vec![*location],
+            OperationKind::DropItems { drop_from, .. } => vec![*drop_from],
+            OperationKind::ImportInventory { node_location, .. }
+            | OperationKind::Craft { node_location, .. }
+            | OperationKind::Smelt { node_location, .. }
+            | OperationKind::CollectSmelted { node_location, .. } => vec![*node_location],
+            _ => self.shulker_station_location().into_iter().collect(),
+        }
+    }
+
+    pub fn crafting_station_location(&self) -> Option<Location> {
+        match &self.kind {
+            OperationKind::Craft { node_location, .. } => Some(*node_location),
+            _ => None,
+        }
+    }
+
     pub fn requires_clear_inventory(&self) -> bool {
         match &self.kind {
             OperationKind::ScanSigns { .. } => false,
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+// Cost added to a reported edge for each failure, halved every half-life until it expires
+const PENALTY_COST: u32 = 1000;
+const PENALTY_HALF_LIFE_MINUTES: i64 = 15;
+const PENALTY_MAX_HALVINGS: i64 = 6;
+
+// Edges are penalized in both directions, so the key doesn't depend on which way it was walked
+pub fn edge_key(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_owned(), b.to_owned())
+    } else {
+        (b.to_owned(), a.to_owned())
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EdgePenalty {
+    pub source_node: String,
+    pub destination_node: String,
+    pub failures: u32,
+    pub first_failed_at: DateTime<Utc>,
+    pub last_failed_at: DateTime<Utc>,
+}
+
+impl EdgePenalty {
+    pub fn cost(&self, now: DateTime<Utc>) -> u32 {
+        let halvings = (now - self.last_failed_at).num_minutes() / PENALTY_HALF_LIFE_MINUTES;
+
+        if halvings >= PENALTY_MAX_HALVINGS {
+            return 0;
+        }
+
+        PENALTY_COST.saturating_mul(self.failures) >> halvings
+    }
+}
+
+#[derive(Default)]
+pub struct PathPenaltyState {
+    penalties: HashMap<(String, String), EdgePenalty>,
+}
+
+impl PathPenaltyState {
+    pub fn record_failure(&mut self, source_node: &str, destination_node: &str) -> &EdgePenalty {
+        let now = Utc::now();
+
+        let penalty = self
+            .penalties
+            .entry(edge_key(source_node, destination_node))
+            .or_insert_with(|| EdgePenalty {
+                source_node: source_node.to_owned(),
+                destination_node: destination_node.to_owned(),
+                failures: 0,
+                first_failed_at: now,
+                last_failed_at: now,
+            });
+
+        penalty.failures += 1;
+        penalty.last_failed_at = now;
+
+        penalty
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EdgePenalty> {
+        self.penalties.values()
+    }
+
+    pub fn purge_expired_penalties(&mut self) {
+        let cutoff =
+            Utc::now() - Duration::minutes(PENALTY_HALF_LIFE_MINUTES * PENALTY_MAX_HALVINGS);
+
+        self.penalties
+            .retain(|_key, penalty| penalty.last_failed_at > cutoff);
+    }
+
+    pub fn current_costs(&self) -> HashMap<(String, String), u32> {
+        let now = Utc::now();
+
+        self.penalties
+            .iter()
+            .map(|(key, penalty)| (key.clone(), penalty.cost(now)))
+            .filter(|(_key, cost)| *cost > 0)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn penalty(failures: u32, last_failed_at: DateTime<Utc>) -> EdgePenalty {
+        EdgePenalty {
+            source_node: String::from("Hallway A"),
+            destination_node: String::from("Hallway B"),
+            failures,
+            first_failed_at: last_failed_at,
+            last_failed_at,
+        }
+    }
+
+    #[test]
+    fn costs_more_for_each_failure() {
+        let now = Utc::now();
+
+        assert_eq!(penalty(1, now).cost(now), PENALTY_COST);
+        assert_eq!(penalty(3, now).cost(now), 3 * PENALTY_COST);
+    }
+
+    #[test]
+    fn halves_every_half_life() {
+        let now = Utc::now();
+        let minutes_ago = |minutes| penalty(4, now - Duration::minutes(minutes));
+
+        assert_eq!(minutes_ago(14).cost(now), 4 * PENALTY_COST);
+        assert_eq!(minutes_ago(15).cost(now), 2 * PENALTY_COST);
+        assert_eq!(minutes_ago(30).cost(now), PENALTY_COST);
+        assert_eq!(minutes_ago(75).cost(now), 4 * PENALTY_COST / 32);
+    }
+
+    #[test]
+    fn expires_after_the_last_halving() {
+        let now = Utc::now();
+        let expired_at = now - Duration::minutes(PENALTY_HALF_LIFE_MINUTES * PENALTY_MAX_HALVINGS);
+
+        assert_eq!(penalty(1000, expired_at).cost(now), 0);
+    }
+
+    #[test]
+    fn saturates_instead_of_overflowing() {
+        let now = Utc::now();
+
+        assert_eq!(penalty(u32::MAX, now).cost(now), u32::MAX);
+    }
+
+    #[test]
+    fn keys_edges_the_same_both_ways() {
+        assert_eq!(edge_key("b", "a"), edge_key("a", "b"));
+        assert_eq!(edge_key("a", "b"), (String::from("a"), String::from("b")));
+    }
+}
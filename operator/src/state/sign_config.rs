@@ -1,18 +1,29 @@
 use std::{
-    collections::HashMap,
+    cmp::{max, min},
+    collections::{BTreeSet, HashMap, HashSet},
     num::ParseIntError,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, MutexGuard},
 };
 
+use pathfinding::directed::{
+    bfs::bfs_reach, strongly_connected_components::strongly_connected_components,
+};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::types::{Dimension, Location, Vec2, Vec3};
+use crate::{
+    pathfinding::{PathCacheMetrics, PathCacheStats, PathGraph, TravelEstimator},
+    state::sign_config_history::SignConfigHistory,
+    types::{Dimension, Direction, Location, Vec2, Vec3},
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Sign {
     pub lines: [String; 4],
     pub location: Location,
+    // Direction the side of the sign holding these lines faces, when the agent could tell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facing: Option<Direction>,
 }
 
 // Sign Syntax
@@ -22,6 +33,12 @@ pub struct Sign {
 // Examples: SSS +0,+2,+0; SSS -0,-2,-0; SSS
 // Location offset is an absolute change in X, Y, and Z, not dependent on sign orientation
 
+// Alternatively, SSS^[ (+/-)int,(+/-)int,(+/-)int]
+// Examples: SSS^ +2,+0,+0; SSS^ 0,-1,-3
+// Location offset is forward, up and right relative to the way the sign faces, where forward is
+// out of the sign's text and right is a quarter turn clockwise from forward
+// Lets the same build be placed in any rotation; offsets on other lines stay absolute
+
 // Line 2: Sign Type
 // Examples: path node
 
@@ -95,6 +112,35 @@ pub struct Sign {
 // nether_node
 // overworld_node
 
+/*
+ * Link Signs
+ * These signs indicate that agents can walk directly between two pathfinding nodes
+ * Links are two-way unless marked one-way, in which case they only lead from the first node to
+ * the second. The cost of a link is the distance between the nodes unless one is given.
+ */
+
+// Line 2: Sign type "link", optionally followed by "one-way" and / or a cost
+// Line 3: Node the link starts at
+// Line 4: Node the link leads to
+
+// Example:
+// SSS
+// link one-way 40
+// Hallway A
+// Hallway B
+
+/*
+ * Auto Link Signs
+ * These signs link every pathfinding node in the sign's dimension to every other node in that
+ * dimension, for areas where agents can walk in a straight line between any two nodes
+ */
+
+// Line 2: Sign type "auto link"
+
+// Example:
+// SSS
+// auto link
+
 /*
  * Shulker Station Sign
  * These signs indicate that a pathfinding node is a station for loading / unloading shulkers
@@ -108,6 +154,57 @@ pub struct Sign {
 // shulker station
 // Hallway A
 
+/*
+ * Crafting Station Signs
+ * These signs indicate that a given location is a crafting table agents can craft at
+ * This crafting table must be reachable from the given pathfinding node
+ * Only one of these is permitted for a given pathfinding node
+ */
+
+// Line 2: Sign type "crafting station"
+// Line 3: Pathfinding Node Name
+// Line 4: Unused
+
+// Example
+// SSS 0,-1,1
+// crafting station
+// Hallway A
+// (4th line empty)
+
+/*
+ * Furnace Station Signs
+ * These signs indicate a row of furnaces agents can smelt in, all reachable from the given
+ * pathfinding node
+ * The effective location of the sign is the first furnace, with the rest of the row extending the
+ * given direction from it. Several rows may be given for one node with more signs.
+ */
+
+// Line 2: Sign type "furnace station"
+// Line 3: Pathfinding Node Name
+// Line 4: Number of furnaces and the direction the row extends in
+
+// Example
+// SSS 0,-1,1
+// furnace station
+// Hallway A
+// 4 east
+
+/*
+ * Tags Signs
+ * These signs attach tags to a pathfinding node so destinations can be addressed by tag
+ * Several tags signs may point at the same node
+ */
+
+// Line 2: Sign type "tags"
+// Line 3: Node to tag
+// Line 4: Tags, separated by spaces or commas
+
+// Example:
+// SSS
+// tags
+// Hallway A
+// farm, public
+
 /*
  * Storage Complex Signs
  * These signs indicate an area of storage containers to be used by the network
@@ -116,17 +213,54 @@ pub struct Sign {
 
 // Line 2: Sign type "storage complex"
 // Line 3: Offset similar to line 1 of complex end. This offset is specified from the effective location of the sign
+//         Optionally followed by <layers>x<stride> to stack several floors, each <stride> blocks
+//         above the last, and by where containers are opened from: above (default), below,
+//         north, east, south or west. Floors opened from a side are one row deep in that
+//         direction.
 // Line 4: Name of complex
 
+// Example:
+// SSS 0,-1,0
+// storage complex
+// 8,0,8 3x2 above
+// Main Floor
+
 /*
  * Storage Tower Signs
- * These signs indicate a 9x9 tower of chests with a hole in the middle
+ * These signs indicate a square tower of chests with a hole in the middle, 9x9 unless another
+ * radius is given
  */
 
 // Line 2: Sign type: "storage tower"
-// Line 3: Height
+// Line 3: Height, optionally followed by r<radius> and skip <layers> (comma separated, counted
+//         from 0 at the bottom) for layers without containers
 // Name of tower
 
+// Example:
+// SSS
+// storage tower
+// 12 r3 skip 4,8
+// Tower A
+
+/*
+ * Storage Wall Signs
+ * These signs indicate a vertical wall of containers facing a corridor
+ * The effective location of the sign is the bottom container at one end of the wall. The wall
+ * extends towards positive X (north / south facing walls) or positive Z (east / west facing walls)
+ * Agents stand in a row in front of the wall, the given number of blocks away, at the bottom
+ * container's Y level
+ */
+
+// Line 2: Sign type "storage wall"
+// Line 3: Width x height, facing and standing row offset
+// Line 4: Name of wall
+
+// Example:
+// SSS 0,-1,0
+// storage wall
+// 9x4 north 2
+// Barrel Wall
+
 #[derive(Debug)]
 pub enum ParsedSign {
     PathfindingNode {
@@ -149,16 +283,53 @@ pub enum ParsedSign {
     ShulkerStation {
         node_name: String,
     },
+    CraftingStation {
+        effective_location: Vec3,
+        node_name: String,
+    },
+    FurnaceStation {
+        effective_location: Vec3,
+        node_name: String,
+        count: u32,
+        direction: Direction,
+    },
+    Tags {
+        node_name: String,
+        tags: Vec<String>,
+    },
+    Link {
+        source_node_name: String,
+        destination_node_name: String,
+        one_way: bool,
+        cost: Option<u32>,
+    },
+    AutoLink {
+        dimension: Dimension,
+    },
     StorageComplex {
         dimension: Dimension,
         y_level: i32,
         bounds: (Vec2, Vec2),
+        layers: u32,
+        stride: i32,
+        open_from: OpenFrom,
         name: String,
     },
     StorageTower {
         dimension: Dimension,
         origin: Vec3,
         height: u32,
+        radius: u32,
+        skipped_layers: Vec<u32>,
+        name: String,
+    },
+    StorageWall {
+        dimension: Dimension,
+        origin: Vec3,
+        width: u32,
+        height: u32,
+        facing: Direction,
+        standoff: u32,
         name: String,
     },
 }
@@ -170,12 +341,28 @@ pub enum SignParseError {
     NoMarker,
     #[error("Failed to parse offset")]
     OffsetParseFailed,
+    #[error("Relative offsets need the sign's facing, which the agent did not report")]
+    UnknownFacing,
     #[error("Sign type unknown")]
     UnknownSignType,
     #[error("Name must not be empty")]
     NameEmpty,
     #[error("Unable to parse height")]
     BadHeight,
+    #[error("Unable to parse wall layout, expected e.g. 9x4 north 2")]
+    BadWallLayout,
+    #[error("Unable to parse link options, expected e.g. link one-way 40")]
+    BadLinkOptions,
+    #[error("Unable to parse tower layout, expected e.g. 12 r3 skip 4,8")]
+    BadTowerLayout,
+    #[error("Unable to parse floor layout, expected e.g. 8,0,8 3x2 above")]
+    BadFloorLayout,
+    #[error("Floors opened from the side must be a single row deep in that direction")]
+    SideOpenedFloorTooDeep,
+    #[error("No tags given, expected e.g. farm public")]
+    NoTags,
+    #[error("Unable to parse furnace row, expected e.g. 4 east")]
+    BadFurnaceRow,
 }
 
 #[derive(Error, Debug, Serialize)]
@@ -185,6 +372,290 @@ pub enum SignConfigValidationError {
     DuplicatePathfindingNode { name: String },
     #[error("Referenced node {name} is unknown")]
     UnknownNode { name: String },
+    #[error("Nodes {source_node} and {destination_node} are linked but in different dimensions")]
+    LinkAcrossDimensions {
+        source_node: String,
+        destination_node: String,
+    },
+    #[error("Node {name} already has a pickup chest")]
+    DuplicatePickup { name: String },
+    #[error("Node {name} already has a drop-off location")]
+    DuplicateDropOff { name: String },
+    #[error("Node {name} already has a portal")]
+    DuplicatePortal { name: String },
+    #[error("Node {name} already has a crafting station")]
+    DuplicateCraftingStation { name: String },
+    #[error("Complex {name} overlaps complex {other_name}")]
+    OverlappingComplexes { name: String, other_name: String },
+    #[error("Node {name} can't be reached from the rest of the graph")]
+    UnreachableNode { name: String },
+    #[error("Node {name} has no path back to the rest of the graph")]
+    NoReturnPath { name: String },
+    #[error("Node {name} is not connected to the rest of the graph")]
+    DisconnectedNode { name: String },
+    #[error("Portal from {source_node} to {destination_node} has no return path")]
+    PortalWithoutReturn {
+        source_node: String,
+        destination_node: String,
+    },
+    #[error("Complex {name} has no shulker station in its dimension")]
+    NoShulkerStation { name: String },
+}
+
+impl SignParseError {
+    // Line of the sign the error is on, starting from 1
+    fn line(&self) -> usize {
+        match self {
+            SignParseError::NoMarker
+            | SignParseError::OffsetParseFailed
+            | SignParseError::UnknownFacing => 1,
+            SignParseError::UnknownSignType | SignParseError::BadLinkOptions => 2,
+            SignParseError::NameEmpty
+            | SignParseError::BadHeight
+            | SignParseError::BadWallLayout
+            | SignParseError::BadTowerLayout
+            | SignParseError::BadFloorLayout
+            | SignParseError::SideOpenedFloorTooDeep => 3,
+            SignParseError::NoTags | SignParseError::BadFurnaceRow => 4,
+        }
+    }
+}
+
+// A problem with a single sign, with enough context to find and fix it in game
+#[derive(Debug, Serialize)]
+pub struct SignDiagnostic<E> {
+    #[serde(flatten)]
+    pub error: E,
+    pub message: String,
+    pub location: Location,
+    pub lines: [String; 4],
+    // Line of the sign the problem is on, starting from 1
+    pub line: usize,
+    pub suggestion: Option<String>,
+}
+
+pub type SignParseFailure = SignDiagnostic<SignParseError>;
+pub type SignValidationFailure = SignDiagnostic<SignConfigValidationError>;
+
+impl<E: std::fmt::Display> SignDiagnostic<E> {
+    fn new(sign: &Sign, line: usize, error: E) -> Self {
+        SignDiagnostic {
+            message: error.to_string(),
+            error,
+            location: sign.location,
+            lines: sign.lines.clone(),
+            line,
+            suggestion: None,
+        }
+    }
+
+    fn with_suggestion(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+}
+
+const SIGN_TYPES: &[&str] = &[
+    "path node",
+    "pickup",
+    "drop-off",
+    "portal",
+    "shulker station",
+    "crafting station",
+    "furnace station",
+    "tags",
+    "link",
+    "auto link",
+    "storage complex",
+    "storage tower",
+    "storage wall",
+];
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous_row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_row[j] + usize::from(a_char != *b_char);
+            row.push(substitution.min(previous_row[j + 1] + 1).min(row[j] + 1));
+        }
+
+        previous_row = row;
+    }
+
+    previous_row[b.len()]
+}
+
+// Closest candidate to a misspelt name, if one is close enough to likely be a typo
+fn closest_match<'a>(input: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let max_distance = (input.chars().count() / 3).max(2);
+
+    candidates
+        .map(|candidate| (edit_distance(input, candidate), candidate))
+        .filter(|(distance, _candidate)| *distance <= max_distance)
+        .min_by_key(|(distance, _candidate)| *distance)
+        .map(|(_distance, candidate)| candidate.to_owned())
+}
+
+fn unknown_node(
+    sign: &Sign,
+    line: usize,
+    name: &str,
+    node_names: &[String],
+) -> SignValidationFailure {
+    SignDiagnostic::new(
+        sign,
+        line,
+        SignConfigValidationError::UnknownNode {
+            name: name.to_owned(),
+        },
+    )
+    .with_suggestion(closest_match(name, node_names.iter().map(String::as_str)))
+}
+
+// Parses the options following "link": "[one-way][ <cost>]"
+fn parse_link_options(options: &str) -> Result<(bool, Option<u32>), SignParseError> {
+    let mut one_way = false;
+    let mut cost = None;
+
+    for part in options.split_whitespace() {
+        if part == "one-way" {
+            one_way = true;
+        } else {
+            cost = Some(part.parse().map_err(|_| SignParseError::BadLinkOptions)?);
+        }
+    }
+
+    Ok((one_way, cost))
+}
+
+// Parses "<height>[ r<radius>][ skip <layer>,<layer>...]"
+fn parse_tower_layout(layout: &str) -> Result<(u32, u32, Vec<u32>), SignParseError> {
+    let mut parts = layout.split_whitespace();
+
+    let height = parts
+        .next()
+        .ok_or(SignParseError::BadHeight)?
+        .parse()
+        .map_err(|_| SignParseError::BadHeight)?;
+    let mut radius = 4;
+    let mut skipped_layers = vec![];
+
+    while let Some(part) = parts.next() {
+        if let Some(radius_str) = part.strip_prefix('r') {
+            radius = radius_str
+                .parse()
+                .map_err(|_| SignParseError::BadTowerLayout)?;
+        } else if part == "skip" {
+            skipped_layers = parts
+                .next()
+                .ok_or(SignParseError::BadTowerLayout)?
+                .split(',')
+                .map(|layer| layer.parse())
+                .collect::<Result<Vec<u32>, ParseIntError>>()
+                .map_err(|_| SignParseError::BadTowerLayout)?;
+        } else {
+            return Err(SignParseError::BadTowerLayout);
+        }
+    }
+
+    if radius == 0 {
+        return Err(SignParseError::BadTowerLayout);
+    }
+
+    Ok((height, radius, skipped_layers))
+}
+
+// Parses "<offset>[ <layers>x<stride>][ <open from>]"
+fn parse_floor_layout(layout: &str) -> Result<(Vec3, u32, i32, OpenFrom), SignParseError> {
+    let mut parts = layout.split_whitespace();
+
+    let offset = parts
+        .next()
+        .ok_or(SignParseError::BadFloorLayout)
+        .and_then(|offset| parse_offset(offset).map_err(|_| SignParseError::BadFloorLayout))?;
+    let mut layers = 1;
+    let mut stride = 0;
+    let mut open_from = OpenFrom::Above;
+
+    for part in parts {
+        if let Some((layers_str, stride_str)) = part.split_once('x') {
+            layers = layers_str
+                .parse()
+                .map_err(|_| SignParseError::BadFloorLayout)?;
+            stride = stride_str
+                .parse()
+                .map_err(|_| SignParseError::BadFloorLayout)?;
+        } else {
+            open_from = match part {
+                "above" => OpenFrom::Above,
+                "below" => OpenFrom::Below,
+                direction => OpenFrom::Side(
+                    direction
+                        .parse()
+                        .map_err(|_| SignParseError::BadFloorLayout)?,
+                ),
+            };
+        }
+    }
+
+    if layers == 0 || (layers > 1 && stride == 0) {
+        return Err(SignParseError::BadFloorLayout);
+    }
+
+    Ok((offset, layers, stride, open_from))
+}
+
+// Parses "<width>x<height> <facing> <standoff>"
+fn parse_wall_layout(layout: &str) -> Result<(u32, u32, Direction, u32), SignParseError> {
+    let parts = layout.split_whitespace().collect::<Vec<_>>();
+
+    if parts.len() != 3 {
+        return Err(SignParseError::BadWallLayout);
+    }
+
+    let (width, height) = parts[0]
+        .split_once('x')
+        .ok_or(SignParseError::BadWallLayout)?;
+
+    let width: u32 = width.parse().map_err(|_| SignParseError::BadWallLayout)?;
+    let height: u32 = height.parse().map_err(|_| SignParseError::BadWallLayout)?;
+    let facing: Direction = parts[1]
+        .parse()
+        .map_err(|_| SignParseError::BadWallLayout)?;
+    let standoff: u32 = parts[2]
+        .parse()
+        .map_err(|_| SignParseError::BadWallLayout)?;
+
+    if width == 0 || height == 0 || standoff == 0 {
+        return Err(SignParseError::BadWallLayout);
+    }
+
+    Ok((width, height, facing, standoff))
+}
+
+fn parse_furnace_row(row: &str) -> Result<(u32, Direction), SignParseError> {
+    let parts = row.split_whitespace().collect::<Vec<_>>();
+
+    if parts.len() != 2 {
+        return Err(SignParseError::BadFurnaceRow);
+    }
+
+    let count: u32 = parts[0]
+        .parse()
+        .map_err(|_| SignParseError::BadFurnaceRow)?;
+    let direction: Direction = parts[1]
+        .parse()
+        .map_err(|_| SignParseError::BadFurnaceRow)?;
+
+    if count == 0 {
+        return Err(SignParseError::BadFurnaceRow);
+    }
+
+    Ok((count, direction))
 }
 
 fn parse_offset(offset: &str) -> Result<Vec3, SignParseError> {
@@ -214,7 +685,22 @@ impl TryFrom<&Sign> for ParsedSign {
             return Err(SignParseError::NoMarker);
         }
 
-        let loc_offset = if let Some(offset) = s.lines[0].strip_prefix("SSS ") {
+        let loc_offset = if let Some(relative) = s.lines[0].strip_prefix("SSS^") {
+            let facing = s.facing.ok_or(SignParseError::UnknownFacing)?;
+            let offset = match relative.strip_prefix(" ") {
+                Some(offset) => parse_offset(offset)?,
+                None if relative.is_empty() => Vec3 { x: 0, y: 0, z: 0 },
+                None => return Err(SignParseError::OffsetParseFailed),
+            };
+
+            facing.offset() * offset.x
+                + Vec3 {
+                    x: 0,
+                    y: offset.y,
+                    z: 0,
+                }
+                + facing.clockwise().offset() * offset.z
+        } else if let Some(offset) = s.lines[0].strip_prefix("SSS ") {
             parse_offset(offset)?
         } else {
             Vec3 { x: 0, y: 0, z: 0 }
@@ -269,9 +755,67 @@ impl TryFrom<&Sign> for ParsedSign {
 
                 Ok(ParsedSign::ShulkerStation { node_name })
             }
+            "crafting station" => {
+                let node_name = s.lines[2].clone();
+
+                Ok(ParsedSign::CraftingStation {
+                    node_name,
+                    effective_location: effective_location.vec3,
+                })
+            }
+            "furnace station" => {
+                let node_name = s.lines[2].clone();
+                let (count, direction) = parse_furnace_row(&s.lines[3])?;
+
+                Ok(ParsedSign::FurnaceStation {
+                    node_name,
+                    effective_location: effective_location.vec3,
+                    count,
+                    direction,
+                })
+            }
+            "tags" => {
+                let node_name = s.lines[2].clone();
+                let tags = s.lines[3]
+                    .split([' ', ','])
+                    .filter(|tag| !tag.is_empty())
+                    .map(|tag| tag.to_lowercase())
+                    .collect::<Vec<_>>();
+
+                if tags.is_empty() {
+                    return Err(SignParseError::NoTags);
+                }
+
+                Ok(ParsedSign::Tags { node_name, tags })
+            }
+            "auto link" => Ok(ParsedSign::AutoLink {
+                dimension: effective_location.dim,
+            }),
+            sign_type if sign_type == "link" || sign_type.starts_with("link ") => {
+                let (one_way, cost) = parse_link_options(&sign_type["link".len()..])?;
+
+                Ok(ParsedSign::Link {
+                    source_node_name: s.lines[2].clone(),
+                    destination_node_name: s.lines[3].clone(),
+                    one_way,
+                    cost,
+                })
+            }
             "storage complex" => {
                 let name = s.lines[3].clone();
-                let second_offset = parse_offset(s.lines[2].as_str())?;
+                let (second_offset, layers, stride, open_from) =
+                    parse_floor_layout(s.lines[2].as_str())?;
+
+                // Containers are opened from the block beside them, which for any row but the
+                // outermost is the next container over
+                if let OpenFrom::Side(direction) = open_from {
+                    let depth = second_offset.x * direction.offset().x
+                        + second_offset.z * direction.offset().z;
+
+                    if depth != 0 {
+                        return Err(SignParseError::SideOpenedFloorTooDeep);
+                    }
+                }
 
                 Ok(ParsedSign::StorageComplex {
                     name,
@@ -281,26 +825,54 @@ impl TryFrom<&Sign> for ParsedSign {
                         effective_location.vec3.into(),
                         (effective_location.vec3 + second_offset).into(),
                     ),
+                    layers,
+                    stride,
+                    open_from,
+                })
+            }
+            "storage tower" => {
+                let (height, radius, skipped_layers) = parse_tower_layout(&s.lines[2])?;
+
+                Ok(ParsedSign::StorageTower {
+                    dimension: effective_location.dim,
+                    origin: effective_location.vec3,
+                    height,
+                    radius,
+                    skipped_layers,
+                    name: s.lines[3].clone(),
+                })
+            }
+            "storage wall" => {
+                let (width, height, facing, standoff) = parse_wall_layout(&s.lines[2])?;
+
+                Ok(ParsedSign::StorageWall {
+                    dimension: effective_location.dim,
+                    origin: effective_location.vec3,
+                    width,
+                    height,
+                    facing,
+                    standoff,
+                    name: s.lines[3].clone(),
                 })
             }
-            "storage tower" => Ok(ParsedSign::StorageTower {
-                dimension: effective_location.dim,
-                origin: effective_location.vec3,
-                height: s.lines[2].parse().map_err(|_| SignParseError::BadHeight)?,
-                name: s.lines[3].clone(),
-            }),
             _ => Err(SignParseError::UnknownSignType),
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, PartialEq)]
 pub struct Portal {
     pub vec3: Vec3,
     pub destination_node_name: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, PartialEq)]
+pub struct NodeLink {
+    pub node_name: String,
+    pub cost: u32,
+}
+
+#[derive(Serialize, PartialEq)]
 pub struct PathfindingNode {
     pub location: Location,
     pub name: String,
@@ -308,80 +880,730 @@ pub struct PathfindingNode {
     pub dropoff: Option<Vec3>,
     pub portal: Option<Portal>,
     pub shulker_station: bool,
+    pub crafting_table: Option<Vec3>,
+    pub furnaces: Vec<Vec3>,
+    pub tags: BTreeSet<String>,
+    // Nodes that can be walked to directly from this one
+    pub links: Vec<NodeLink>,
 }
 
-#[derive(Serialize)]
+impl PathfindingNode {
+    pub fn free_furnaces<'a>(
+        &'a self,
+        busy_furnaces: &'a HashSet<Location>,
+    ) -> impl Iterator<Item = Location> + 'a {
+        self.furnace_locations()
+            .filter(|furnace| !busy_furnaces.contains(furnace))
+    }
+
+    fn busy_furnaces<'a>(
+        &'a self,
+        busy_furnaces: &'a HashSet<Location>,
+    ) -> impl Iterator<Item = Location> + 'a {
+        self.furnace_locations()
+            .filter(|furnace| busy_furnaces.contains(furnace))
+    }
+
+    fn furnace_locations(&self) -> impl Iterator<Item = Location> + '_ {
+        self.furnaces.iter().map(|furnace| Location {
+            dim: self.location.dim,
+            vec3: *furnace,
+        })
+    }
+
+    fn add_link(&mut self, node_name: &str, cost: u32) {
+        if let Some(link) = self
+            .links
+            .iter_mut()
+            .find(|link| link.node_name == node_name)
+        {
+            link.cost = link.cost.min(cost);
+        } else {
+            self.links.push(NodeLink {
+                node_name: node_name.to_owned(),
+                cost,
+            });
+        }
+    }
+}
+
+// Cost of walking between two points in the same dimension
+pub fn walking_cost(a: Vec3, b: Vec3) -> u32 {
+    (a.dist(b).round() as u32).max(1)
+}
+
+// Where the containers of a flat floor are opened from, relative to each container
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenFrom {
+    Above,
+    Below,
+    Side(Direction),
+}
+
+impl OpenFrom {
+    pub fn offset(&self) -> Vec3 {
+        match self {
+            OpenFrom::Above => Vec3 { x: 0, y: 1, z: 0 },
+            OpenFrom::Below => Vec3 { x: 0, y: -1, z: 0 },
+            OpenFrom::Side(direction) => direction.offset(),
+        }
+    }
+}
+
+#[derive(Serialize, PartialEq)]
 pub enum StorageComplex {
     FlatFloor {
         dimension: Dimension,
         name: String,
         y_level: i32,
         bounds: (Vec2, Vec2),
+        layers: u32,
+        stride: i32,
+        open_from: OpenFrom,
     },
     Tower {
         dimension: Dimension,
         name: String,
         origin: Vec3,
         height: u32,
+        radius: u32,
+        skipped_layers: Vec<u32>,
+    },
+    Wall {
+        dimension: Dimension,
+        name: String,
+        origin: Vec3,
+        width: u32,
+        height: u32,
+        facing: Direction,
+        standoff: u32,
     },
 }
 
-#[derive(Serialize)]
-pub struct CompiledSignConfig {
-    pub nodes: HashMap<String, PathfindingNode>,
-    pub complexes: HashMap<String, StorageComplex>,
-
-    pub sign_parse_errors: Vec<SignParseError>,
-    pub validation_errors: Vec<SignConfigValidationError>,
-}
-
-pub struct SignConfigState {
-    signs: Vec<Sign>,
-    cached_config: Mutex<Option<Arc<CompiledSignConfig>>>,
-}
+impl StorageComplex {
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            StorageComplex::FlatFloor { dimension, .. } => *dimension,
+            StorageComplex::Tower { dimension, .. } => *dimension,
+            StorageComplex::Wall { dimension, .. } => *dimension,
+        }
+    }
 
-impl Default for SignConfigState {
-    fn default() -> Self {
-        SignConfigState {
-            signs: Default::default(),
-            cached_config: Default::default(),
+    pub fn name(&self) -> &str {
+        match self {
+            StorageComplex::FlatFloor { name, .. } => name,
+            StorageComplex::Tower { name, .. } => name,
+            StorageComplex::Wall { name, .. } => name,
         }
     }
-}
 
-impl SignConfigState {
-    pub fn clear_area(&mut self, dimension: Dimension, start: Vec2, end: Vec2) {
-        self.signs.retain(|sign| {
-            !(sign.location.dim == dimension
-                && Vec2::from(sign.location.vec3).contained_by(start, end, 0))
-        });
+    // Corners of the area the complex's containers cover, viewed from above
+    pub fn footprint(&self) -> (Vec2, Vec2) {
+        match self {
+            StorageComplex::FlatFloor { bounds, .. } => *bounds,
+            StorageComplex::Tower { origin, radius, .. } => {
+                let radius = *radius as i32;
+
+                (
+                    Vec2 {
+                        x: origin.x - radius,
+                        z: origin.z - radius,
+                    },
+                    Vec2 {
+                        x: origin.x + radius,
+                        z: origin.z + radius,
+                    },
+                )
+            }
+            StorageComplex::Wall {
+                origin,
+                width,
+                facing,
+                ..
+            } => (
+                Vec2::from(*origin),
+                Vec2::from(*origin + Self::wall_axis(*facing) * (*width as i32 - 1)),
+            ),
+        }
+    }
 
-        self.set_dirty();
+    // Y levels of each layer of a flat floor
+    pub fn floor_layer_levels(y_level: i32, layers: u32, stride: i32) -> Vec<i32> {
+        (0..layers as i32)
+            .map(|layer| y_level + layer * stride)
+            .collect()
     }
 
-    pub fn add_sign(&mut self, sign: Sign) {
-        self.signs.push(sign);
+    // Direction the wall runs in from its origin
+    fn wall_axis(facing: Direction) -> Vec3 {
+        match facing {
+            Direction::North | Direction::South => Vec3 { x: 1, y: 0, z: 0 },
+            Direction::East | Direction::West => Vec3 { x: 0, y: 0, z: 1 },
+        }
+    }
 
-        self.set_dirty();
+    // First and last positions of the row agents stand in to access a wall
+    pub fn wall_standing_row(
+        origin: Vec3,
+        width: u32,
+        facing: Direction,
+        standoff: u32,
+    ) -> (Vec3, Vec3) {
+        let row_start = origin + facing.offset() * (standoff as i32);
+        let row_end = row_start + Self::wall_axis(facing) * (width as i32 - 1);
+
+        (row_start, row_end)
     }
 
-    fn generate_config(&self) -> CompiledSignConfig {
-        let (parsed_signs, parse_errors): (Vec<_>, Vec<_>) = self
-            .signs
-            .iter()
-            .map(|sign| sign.try_into())
-            .partition(Result::is_ok);
+    // Every container location in this complex, paired with the position it is opened from
+    pub fn containers(&self) -> Vec<(Location, Vec3)> {
+        let mut containers = vec![];
 
-        let parsed_signs: Vec<ParsedSign> = parsed_signs.into_iter().map(Result::unwrap).collect();
-        let sign_parse_errors: Vec<SignParseError> =
-            parse_errors.into_iter().map(Result::unwrap_err).collect();
+        match self {
+            StorageComplex::FlatFloor {
+                dimension,
+                y_level,
+                bounds,
+                layers,
+                stride,
+                open_from,
+                ..
+            } => {
+                let x1 = bounds.0.x;
+                let x2 = bounds.1.x;
+                let z1 = bounds.0.z;
+                let z2 = bounds.1.z;
+
+                for y in Self::floor_layer_levels(*y_level, *layers, *stride) {
+                    for x in min(x1, x2)..=max(x1, x2) {
+                        for z in min(z1, z2)..=max(z1, z2) {
+                            let vec3 = Vec3 { x, y, z };
+
+                            containers.push((
+                                Location {
+                                    vec3,
+                                    dim: *dimension,
+                                },
+                                vec3 + open_from.offset(),
+                            ));
+                        }
+                    }
+                }
+            }
+            StorageComplex::Tower {
+                dimension,
+                origin,
+                height,
+                radius,
+                skipped_layers,
+                ..
+            } => {
+                let radius = *radius as i32;
 
-        let mut validation_errors = Vec::new();
+                for y in (origin.y)..=(origin.y + (*height as i32) - 1) {
+                    if skipped_layers.contains(&((y - origin.y) as u32)) {
+                        continue;
+                    }
+
+                    for x in (origin.x - radius)..=(origin.x + radius) {
+                        for z in (origin.z - radius)..=(origin.z + radius) {
+                            if x == origin.x && z == origin.z {
+                                continue;
+                            }
+
+                            containers.push((
+                                Location {
+                                    vec3: Vec3 { x, y, z },
+                                    dim: *dimension,
+                                },
+                                Vec3 {
+                                    x: origin.x,
+                                    y,
+                                    z: origin.z,
+                                },
+                            ));
+                        }
+                    }
+                }
+            }
+            StorageComplex::Wall {
+                dimension,
+                origin,
+                width,
+                height,
+                facing,
+                standoff,
+                ..
+            } => {
+                let axis = Self::wall_axis(*facing);
+                let (row_start, _row_end) =
+                    Self::wall_standing_row(*origin, *width, *facing, *standoff);
+
+                for i in 0..(*width as i32) {
+                    for y in 0..(*height as i32) {
+                        containers.push((
+                            Location {
+                                vec3: *origin + axis * i + Vec3 { x: 0, y, z: 0 },
+                                dim: *dimension,
+                            },
+                            row_start + axis * i,
+                        ));
+                    }
+                }
+            }
+        }
+
+        containers
+    }
+}
+
+impl ParsedSign {
+    // Name of the node the sign defines, and the line that name is on
+    fn defined_node(&self) -> Option<(&str, usize)> {
+        match self {
+            ParsedSign::PathfindingNode { name, .. } => Some((name, 3)),
+            ParsedSign::StorageComplex { name, .. }
+            | ParsedSign::StorageTower { name, .. }
+            | ParsedSign::StorageWall { name, .. } => Some((name, 4)),
+            _ => None,
+        }
+    }
+
+    // Name of the node the sign adds a station, container or tags to
+    fn attached_node(&self) -> Option<&str> {
+        match self {
+            ParsedSign::PickupChest { node_name, .. }
+            | ParsedSign::DropOffLocation { node_name, .. }
+            | ParsedSign::ShulkerStation { node_name }
+            | ParsedSign::CraftingStation { node_name, .. }
+            | ParsedSign::FurnaceStation { node_name, .. }
+            | ParsedSign::Tags { node_name, .. } => Some(node_name),
+            ParsedSign::Portal {
+                source_node_name, ..
+            } => Some(source_node_name),
+            _ => None,
+        }
+    }
+}
+
+// Checks that only make sense once the whole config has been compiled
+fn validate_layout(
+    parsed_signs: &[(&Sign, ParsedSign)],
+    nodes: &HashMap<String, PathfindingNode>,
+    complexes: &HashMap<String, StorageComplex>,
+) -> Vec<SignValidationFailure> {
+    let mut validation_errors = vec![];
+
+    // Later signs win when defining the same name, matching how the config is compiled
+    let mut node_signs: HashMap<&str, (&Sign, usize)> = HashMap::new();
+    for (source, sign) in parsed_signs.iter() {
+        if let Some((name, line)) = sign.defined_node() {
+            node_signs.insert(name, (*source, line));
+        }
+    }
+
+    // Complexes claim containers in sign order, the first to claim a container keeps it
+    let mut claimed_containers: HashMap<Location, &str> = HashMap::new();
+    let mut complex_names = vec![];
+    for (source, sign) in parsed_signs.iter() {
+        let name = match sign {
+            ParsedSign::StorageComplex { name, .. }
+            | ParsedSign::StorageTower { name, .. }
+            | ParsedSign::StorageWall { name, .. } => name,
+            _ => continue,
+        };
+
+        if complex_names.contains(&name) {
+            continue;
+        }
+        complex_names.push(name);
+
+        let complex = complexes.get(name).unwrap();
+
+        for (location, _open_from) in complex.containers() {
+            match claimed_containers.get(&location) {
+                Some(other_name) => {
+                    validation_errors.push(SignDiagnostic::new(
+                        source,
+                        4,
+                        SignConfigValidationError::OverlappingComplexes {
+                            name: name.clone(),
+                            other_name: other_name.to_string(),
+                        },
+                    ));
+                    break;
+                }
+                None => {
+                    claimed_containers.insert(location, name);
+                }
+            }
+        }
+
+        let has_shulker_station = nodes
+            .values()
+            .any(|node| node.shulker_station && node.location.dim == complex.dimension());
+
+        if !has_shulker_station {
+            validation_errors.push(SignDiagnostic::new(
+                source,
+                2,
+                SignConfigValidationError::NoShulkerStation { name: name.clone() },
+            ));
+        }
+    }
+
+    let successors = |name: &String| -> Vec<String> {
+        let node = nodes.get(name).unwrap();
+
+        node.links
+            .iter()
+            .map(|link| link.node_name.clone())
+            .chain(
+                node.portal
+                    .as_ref()
+                    .map(|portal| portal.destination_node_name.clone()),
+            )
+            .filter(|name| nodes.contains_key(name))
+            .collect()
+    };
+
+    let mut names = nodes.keys().cloned().collect::<Vec<_>>();
+    names.sort();
+
+    // The largest strongly connected component is taken to be the rest of the graph
+    let main_component = strongly_connected_components(&names, successors)
+        .into_iter()
+        .max_by_key(|component| component.len())
+        .unwrap_or_default();
+
+    if let Some(main_node) = main_component.first() {
+        let reachable_from_main = bfs_reach(main_node.clone(), successors).collect::<Vec<_>>();
+
+        for name in names.iter().filter(|name| !main_component.contains(name)) {
+            let reachable = reachable_from_main.contains(name);
+            let returns = bfs_reach(name.clone(), successors).any(|other| other == *main_node);
+
+            let error = match (reachable, returns) {
+                (true, true) => continue,
+                (false, false) => {
+                    SignConfigValidationError::DisconnectedNode { name: name.clone() }
+                }
+                (false, true) => SignConfigValidationError::UnreachableNode { name: name.clone() },
+                (true, false) => SignConfigValidationError::NoReturnPath { name: name.clone() },
+            };
+
+            let (source, line) = node_signs.get(name.as_str()).unwrap();
+            validation_errors.push(SignDiagnostic::new(source, *line, error));
+        }
+    }
+
+    for (source, sign) in parsed_signs.iter() {
+        if let ParsedSign::Portal {
+            source_node_name,
+            destination_node_name,
+            ..
+        } = sign
+        {
+            if !nodes.contains_key(source_node_name) || !nodes.contains_key(destination_node_name) {
+                continue;
+            }
+
+            let returns = bfs_reach(destination_node_name.clone(), successors)
+                .any(|other| other == *source_node_name);
+
+            if !returns {
+                validation_errors.push(SignDiagnostic::new(
+                    source,
+                    4,
+                    SignConfigValidationError::PortalWithoutReturn {
+                        source_node: source_node_name.clone(),
+                        destination_node: destination_node_name.clone(),
+                    },
+                ));
+            }
+        }
+    }
+
+    validation_errors
+}
+
+#[derive(Serialize, Default)]
+pub struct CompiledSignConfig {
+    // Incremented every time the config is rebuilt
+    pub generation: u64,
+    pub nodes: HashMap<String, PathfindingNode>,
+    pub complexes: HashMap<String, StorageComplex>,
+
+    pub sign_parse_errors: Vec<SignParseFailure>,
+    pub validation_errors: Vec<SignValidationFailure>,
+}
+
+impl CompiledSignConfig {
+    // Least busy station, breaking ties by travel to the containers involved
+    fn best_station(
+        &self,
+        is_station: impl Fn(&PathfindingNode) -> bool,
+        occupancy: &HashMap<Location, usize>,
+        involved: &[Location],
+    ) -> Option<&PathfindingNode> {
+        let estimator = TravelEstimator::new(self);
+
+        self.nodes
+            .values()
+            .filter(|node| is_station(node))
+            .min_by_key(|node| {
+                let travel: u32 = involved
+                    .iter()
+                    .map(|location| estimator.estimate(node.location, *location))
+                    .sum();
+
+                (
+                    occupancy.get(&node.location).copied().unwrap_or(0),
+                    travel,
+                    &node.name,
+                )
+            })
+    }
+
+    pub fn best_shulker_station(
+        &self,
+        occupancy: &HashMap<Location, usize>,
+        involved: &[Location],
+    ) -> Option<Location> {
+        self.best_station(|node| node.shulker_station, occupancy, involved)
+            .map(|node| node.location)
+    }
+
+    // Station with the fewest busy furnaces that has one free
+    pub fn best_furnace_station(
+        &self,
+        busy_furnaces: &HashSet<Location>,
+        involved: &[Location],
+    ) -> Option<&PathfindingNode> {
+        let occupancy = self
+            .nodes
+            .values()
+            .map(|node| (node.location, node.busy_furnaces(busy_furnaces).count()))
+            .collect::<HashMap<_, _>>();
+
+        self.best_station(
+            |node| node.free_furnaces(busy_furnaces).next().is_some(),
+            &occupancy,
+            involved,
+        )
+    }
+
+    pub fn best_crafting_station(
+        &self,
+        occupancy: &HashMap<Location, usize>,
+        involved: &[Location],
+    ) -> Option<&PathfindingNode> {
+        self.best_station(|node| node.crafting_table.is_some(), occupancy, involved)
+    }
+}
+
+// Differences between the declared layout and the signs agents have scanned
+#[derive(Serialize)]
+pub struct SignConfigDiff<'a> {
+    // Declared, but not found where agents have scanned
+    pub missing: Vec<&'a Sign>,
+    // Scanned, but not declared
+    pub unexpected: Vec<&'a Sign>,
+    // Declared and scanned at the same location with different text
+    pub changed: Vec<SignChange<'a>>,
+    // Declared in areas agents have not scanned yet
+    pub unverified: Vec<&'a Sign>,
+}
+
+#[derive(Serialize)]
+pub struct SignChange<'a> {
+    pub location: Location,
+    pub declared: &'a [String; 4],
+    pub scanned: &'a [String; 4],
+}
+
+fn is_config_sign(sign: &Sign) -> bool {
+    sign.lines[0].starts_with("SSS")
+}
+
+pub struct SignConfigState {
+    signs: Vec<Sign>,
+    // Signs imported from a declared layout, merged with scanned signs when compiling
+    declared_signs: Vec<Sign>,
+    scanned_regions: Vec<(Dimension, Vec2, Vec2)>,
+    cached_config: Mutex<Option<Arc<CompiledSignConfig>>>,
+    history: Mutex<SignConfigHistory>,
+    cached_path_graph: Mutex<Option<Arc<PathGraph>>>,
+    path_cache_metrics: Arc<PathCacheMetrics>,
+    // Extra cost of edges agents have recently failed to walk, keyed by edge_key
+    edge_penalties: HashMap<(String, String), u32>,
+    // Costs of edges derived from learned travel times, in the direction walked
+    learned_edge_costs: HashMap<(String, String), u32>,
+}
+
+impl Default for SignConfigState {
+    fn default() -> Self {
+        SignConfigState {
+            signs: Default::default(),
+            declared_signs: Default::default(),
+            scanned_regions: Default::default(),
+            cached_config: Default::default(),
+            history: Default::default(),
+            cached_path_graph: Default::default(),
+            path_cache_metrics: Default::default(),
+            edge_penalties: Default::default(),
+            learned_edge_costs: Default::default(),
+        }
+    }
+}
+
+impl SignConfigState {
+    pub fn clear_area(&mut self, dimension: Dimension, start: Vec2, end: Vec2) {
+        self.signs.retain(|sign| {
+            !(sign.location.dim == dimension
+                && Vec2::from(sign.location.vec3).contained_by(start, end, 0))
+        });
+
+        if !self.scanned_regions.contains(&(dimension, start, end)) {
+            self.scanned_regions.push((dimension, start, end));
+        }
+
+        self.set_dirty();
+    }
+
+    pub fn add_sign(&mut self, sign: Sign) {
+        self.signs.push(sign);
+
+        self.set_dirty();
+    }
+
+    pub fn set_declared_signs(&mut self, declared_signs: Vec<Sign>) {
+        self.declared_signs = declared_signs;
+
+        self.set_dirty();
+    }
+
+    fn is_scanned(&self, location: Location) -> bool {
+        self.scanned_regions.iter().any(|(dimension, start, end)| {
+            location.dim == *dimension && Vec2::from(location.vec3).contained_by(*start, *end, 0)
+        })
+    }
+
+    // Scanned signs always win over declared ones. A declared sign is dropped once agents have
+    // scanned the area it is in, when a scanned sign defines the same node or complex, or when it
+    // adds to a node a scanned sign defines, since the node is then set up from scanned signs
+    fn effective_signs(&self) -> Vec<&Sign> {
+        let scanned_names = self
+            .signs
+            .iter()
+            .filter_map(|sign| ParsedSign::try_from(sign).ok())
+            .filter_map(|sign| sign.defined_node().map(|(name, _line)| name.to_owned()))
+            .collect::<HashSet<_>>();
+
+        let declared_signs = self.declared_signs.iter().filter(|sign| {
+            if self.is_scanned(sign.location) {
+                return false;
+            }
+
+            match ParsedSign::try_from(*sign) {
+                Ok(parsed_sign) => {
+                    parsed_sign
+                        .defined_node()
+                        .is_none_or(|(name, _line)| !scanned_names.contains(name))
+                        && parsed_sign
+                            .attached_node()
+                            .is_none_or(|name| !scanned_names.contains(name))
+                }
+                Err(_) => true,
+            }
+        });
+
+        self.signs.iter().chain(declared_signs).collect()
+    }
+
+    // Config signs that make up the compiled config, in a form that can be declared again
+    pub fn export_signs(&self) -> Vec<Sign> {
+        self.effective_signs()
+            .into_iter()
+            .filter(|sign| is_config_sign(sign))
+            .cloned()
+            .collect()
+    }
+
+    pub fn diff(&self) -> SignConfigDiff<'_> {
+        let scanned_at = self
+            .signs
+            .iter()
+            .filter(|sign| is_config_sign(sign))
+            .map(|sign| (sign.location, sign))
+            .collect::<HashMap<_, _>>();
+
+        let mut diff = SignConfigDiff {
+            missing: vec![],
+            unexpected: vec![],
+            changed: vec![],
+            unverified: vec![],
+        };
+
+        for declared in self.declared_signs.iter() {
+            if !self.is_scanned(declared.location) {
+                diff.unverified.push(declared);
+                continue;
+            }
+
+            match scanned_at.get(&declared.location) {
+                Some(scanned) if scanned.lines == declared.lines => {}
+                Some(scanned) => diff.changed.push(SignChange {
+                    location: declared.location,
+                    declared: &declared.lines,
+                    scanned: &scanned.lines,
+                }),
+                None => diff.missing.push(declared),
+            }
+        }
+
+        let declared_locations = self
+            .declared_signs
+            .iter()
+            .map(|sign| sign.location)
+            .collect::<HashSet<_>>();
+
+        diff.unexpected = scanned_at
+            .into_values()
+            .filter(|sign| !declared_locations.contains(&sign.location))
+            .collect();
+
+        diff
+    }
+
+    fn generate_config(&self, generation: u64) -> CompiledSignConfig {
+        let mut parsed_signs: Vec<(&Sign, ParsedSign)> = vec![];
+        let mut sign_parse_errors: Vec<SignParseFailure> = vec![];
+
+        for sign in self.effective_signs() {
+            match ParsedSign::try_from(sign) {
+                Ok(parsed_sign) => parsed_signs.push((sign, parsed_sign)),
+                Err(error) => {
+                    let suggestion = match error {
+                        SignParseError::UnknownSignType => {
+                            closest_match(&sign.lines[1], SIGN_TYPES.iter().copied())
+                        }
+                        _ => None,
+                    };
+
+                    sign_parse_errors.push(
+                        SignDiagnostic::new(sign, error.line(), error).with_suggestion(suggestion),
+                    );
+                }
+            }
+        }
+
+        let mut validation_errors = Vec::new();
 
         let mut nodes = HashMap::new();
 
         // Add all nodes to map first
-        parsed_signs.iter().for_each(|sign| {
+        parsed_signs.iter().for_each(|(source, sign)| {
             match sign {
                 ParsedSign::PathfindingNode {
                     effective_location,
@@ -396,22 +1618,30 @@ impl SignConfigState {
                             dropoff: None,
                             portal: None,
                             shulker_station: false,
+                            crafting_table: None,
+                            furnaces: vec![],
+                            tags: Default::default(),
+                            links: vec![],
                         },
                     );
 
                     if existing_node.is_some() {
-                        validation_errors.push(
+                        validation_errors.push(SignDiagnostic::new(
+                            source,
+                            3,
                             SignConfigValidationError::DuplicatePathfindingNode {
                                 name: name.clone(),
                             },
-                        )
+                        ))
                     }
                 }
                 ParsedSign::StorageComplex {
                     y_level,
                     bounds,
                     dimension,
+                    open_from,
                     name,
+                    ..
                 } => {
                     let existing_node = nodes.insert(
                         name.clone(),
@@ -421,30 +1651,36 @@ impl SignConfigState {
                                 vec3: Vec3 {
                                     x: bounds.0.x,
                                     z: bounds.0.z,
-                                    y: y_level + 1,
-                                },
+                                    y: *y_level,
+                                } + open_from.offset(),
                                 dim: *dimension,
                             },
                             pickup: None,
                             dropoff: None,
                             portal: None,
                             shulker_station: false,
+                            crafting_table: None,
+                            furnaces: vec![],
+                            tags: Default::default(),
+                            links: vec![],
                         },
                     );
 
                     if existing_node.is_some() {
-                        validation_errors.push(
+                        validation_errors.push(SignDiagnostic::new(
+                            source,
+                            4,
                             SignConfigValidationError::DuplicatePathfindingNode {
                                 name: name.clone(),
                             },
-                        )
+                        ))
                     }
                 }
                 ParsedSign::StorageTower {
                     origin,
-                    height: _height,
                     dimension,
                     name,
+                    ..
                 } => {
                     let existing_node = nodes.insert(
                         name.clone(),
@@ -458,15 +1694,63 @@ impl SignConfigState {
                             dropoff: None,
                             portal: None,
                             shulker_station: false,
+                            crafting_table: None,
+                            furnaces: vec![],
+                            tags: Default::default(),
+                            links: vec![],
                         },
                     );
 
                     if existing_node.is_some() {
-                        validation_errors.push(
+                        validation_errors.push(SignDiagnostic::new(
+                            source,
+                            4,
                             SignConfigValidationError::DuplicatePathfindingNode {
                                 name: name.clone(),
                             },
-                        )
+                        ))
+                    }
+                }
+
+                ParsedSign::StorageWall {
+                    dimension,
+                    origin,
+                    width,
+                    facing,
+                    standoff,
+                    name,
+                    ..
+                } => {
+                    let (row_start, _row_end) =
+                        StorageComplex::wall_standing_row(*origin, *width, *facing, *standoff);
+
+                    let existing_node = nodes.insert(
+                        name.clone(),
+                        PathfindingNode {
+                            name: name.clone(),
+                            location: Location {
+                                vec3: row_start,
+                                dim: *dimension,
+                            },
+                            pickup: None,
+                            dropoff: None,
+                            portal: None,
+                            shulker_station: false,
+                            crafting_table: None,
+                            furnaces: vec![],
+                            tags: Default::default(),
+                            links: vec![],
+                        },
+                    );
+
+                    if existing_node.is_some() {
+                        validation_errors.push(SignDiagnostic::new(
+                            source,
+                            4,
+                            SignConfigValidationError::DuplicatePathfindingNode {
+                                name: name.clone(),
+                            },
+                        ))
                     }
                 }
 
@@ -474,8 +1758,10 @@ impl SignConfigState {
             };
         });
 
+        let node_names = nodes.keys().cloned().collect::<Vec<_>>();
+
         // Add all pickups and drop-offs
-        parsed_signs.iter().for_each(|sign| match sign {
+        parsed_signs.iter().for_each(|(source, sign)| match sign {
             ParsedSign::DropOffLocation {
                 node_name,
                 effective_location,
@@ -483,13 +1769,23 @@ impl SignConfigState {
                 let node = nodes.get_mut(node_name);
 
                 if node.is_none() {
-                    validation_errors.push(SignConfigValidationError::UnknownNode {
-                        name: node_name.clone(),
-                    });
+                    validation_errors.push(unknown_node(source, 3, node_name, &node_names));
                     return;
                 }
 
-                node.unwrap().dropoff = Some(*effective_location)
+                let node = node.unwrap();
+
+                if node.dropoff.is_some() {
+                    validation_errors.push(SignDiagnostic::new(
+                        source,
+                        3,
+                        SignConfigValidationError::DuplicateDropOff {
+                            name: node_name.clone(),
+                        },
+                    ));
+                }
+
+                node.dropoff = Some(*effective_location)
             }
             ParsedSign::PickupChest {
                 node_name,
@@ -498,26 +1794,97 @@ impl SignConfigState {
                 let node = nodes.get_mut(node_name);
 
                 if node.is_none() {
-                    validation_errors.push(SignConfigValidationError::UnknownNode {
-                        name: node_name.clone(),
-                    });
+                    validation_errors.push(unknown_node(source, 3, node_name, &node_names));
                     return;
                 }
 
-                node.unwrap().pickup = Some(*effective_location)
+                let node = node.unwrap();
+
+                if node.pickup.is_some() {
+                    validation_errors.push(SignDiagnostic::new(
+                        source,
+                        3,
+                        SignConfigValidationError::DuplicatePickup {
+                            name: node_name.clone(),
+                        },
+                    ));
+                }
+
+                node.pickup = Some(*effective_location)
             }
             ParsedSign::ShulkerStation { node_name } => {
                 let node = nodes.get_mut(node_name);
 
                 if node.is_none() {
-                    validation_errors.push(SignConfigValidationError::UnknownNode {
-                        name: node_name.clone(),
-                    });
+                    validation_errors.push(unknown_node(source, 3, node_name, &node_names));
                     return;
                 }
 
                 node.unwrap().shulker_station = true
             }
+            ParsedSign::CraftingStation {
+                node_name,
+                effective_location,
+            } => {
+                let node = nodes.get_mut(node_name);
+
+                if node.is_none() {
+                    validation_errors.push(unknown_node(source, 3, node_name, &node_names));
+                    return;
+                }
+
+                let node = node.unwrap();
+
+                if node.crafting_table.is_some() {
+                    validation_errors.push(SignDiagnostic::new(
+                        source,
+                        3,
+                        SignConfigValidationError::DuplicateCraftingStation {
+                            name: node_name.clone(),
+                        },
+                    ));
+                }
+
+                node.crafting_table = Some(*effective_location)
+            }
+            ParsedSign::FurnaceStation {
+                node_name,
+                effective_location,
+                count,
+                direction,
+            } => {
+                let node = nodes.get_mut(node_name);
+
+                if node.is_none() {
+                    validation_errors.push(unknown_node(source, 3, node_name, &node_names));
+                    return;
+                }
+
+                let node = node.unwrap();
+                let step = direction.offset();
+
+                for idx in 0..*count as i32 {
+                    let furnace = Vec3 {
+                        x: effective_location.x + step.x * idx,
+                        y: effective_location.y,
+                        z: effective_location.z + step.z * idx,
+                    };
+
+                    if !node.furnaces.contains(&furnace) {
+                        node.furnaces.push(furnace);
+                    }
+                }
+            }
+            ParsedSign::Tags { node_name, tags } => {
+                let node = nodes.get_mut(node_name);
+
+                if node.is_none() {
+                    validation_errors.push(unknown_node(source, 3, node_name, &node_names));
+                    return;
+                }
+
+                node.unwrap().tags.extend(tags.iter().cloned())
+            }
             ParsedSign::Portal {
                 effective_location,
                 source_node_name,
@@ -527,22 +1894,33 @@ impl SignConfigState {
                 let destination_node = nodes.get(destination_node_name);
 
                 if source_node.is_none() {
-                    validation_errors.push(SignConfigValidationError::UnknownNode {
-                        name: source_node_name.clone(),
-                    });
+                    validation_errors.push(unknown_node(source, 3, source_node_name, &node_names));
                     return;
                 }
 
                 if destination_node.is_none() {
                     // Keep portal so it can be scanned, but still report validation error
-                    validation_errors.push(SignConfigValidationError::UnknownNode {
-                        name: destination_node_name.clone(),
-                    });
+                    validation_errors.push(unknown_node(
+                        source,
+                        4,
+                        destination_node_name,
+                        &node_names,
+                    ));
                 }
 
-                let source_node = nodes.get_mut(source_node_name);
+                let source_node = nodes.get_mut(source_node_name).unwrap();
+
+                if source_node.portal.is_some() {
+                    validation_errors.push(SignDiagnostic::new(
+                        source,
+                        3,
+                        SignConfigValidationError::DuplicatePortal {
+                            name: source_node_name.clone(),
+                        },
+                    ));
+                }
 
-                source_node.unwrap().portal = Some(Portal {
+                source_node.portal = Some(Portal {
                     destination_node_name: destination_node_name.clone(),
                     vec3: *effective_location,
                 });
@@ -550,13 +1928,91 @@ impl SignConfigState {
             _ => {}
         });
 
+        // Add all links
+        parsed_signs.iter().for_each(|(source, sign)| match sign {
+            ParsedSign::Link {
+                source_node_name,
+                destination_node_name,
+                one_way,
+                cost,
+            } => {
+                let (source_location, destination_location) = match (
+                    nodes.get(source_node_name),
+                    nodes.get(destination_node_name),
+                ) {
+                    (Some(source_node), Some(destination_node)) => {
+                        (source_node.location, destination_node.location)
+                    }
+                    (source_node, _) => {
+                        let (line, name) = if source_node.is_none() {
+                            (3, source_node_name)
+                        } else {
+                            (4, destination_node_name)
+                        };
+
+                        validation_errors.push(unknown_node(source, line, name, &node_names));
+                        return;
+                    }
+                };
+
+                if source_location.dim != destination_location.dim {
+                    validation_errors.push(SignDiagnostic::new(
+                        source,
+                        2,
+                        SignConfigValidationError::LinkAcrossDimensions {
+                            source_node: source_node_name.clone(),
+                            destination_node: destination_node_name.clone(),
+                        },
+                    ));
+                    return;
+                }
+
+                let cost = cost.unwrap_or_else(|| {
+                    walking_cost(source_location.vec3, destination_location.vec3)
+                });
+
+                nodes
+                    .get_mut(source_node_name)
+                    .unwrap()
+                    .add_link(destination_node_name, cost);
+
+                if !one_way {
+                    nodes
+                        .get_mut(destination_node_name)
+                        .unwrap()
+                        .add_link(source_node_name, cost);
+                }
+            }
+            ParsedSign::AutoLink { dimension } => {
+                let nodes_in_dim = nodes
+                    .values()
+                    .filter(|node| node.location.dim == *dimension)
+                    .map(|node| (node.name.clone(), node.location.vec3))
+                    .collect::<Vec<_>>();
+
+                for (name, vec3) in nodes_in_dim.iter() {
+                    let node = nodes.get_mut(name).unwrap();
+
+                    for (other_name, other_vec3) in nodes_in_dim.iter() {
+                        if other_name != name {
+                            node.add_link(other_name, walking_cost(*vec3, *other_vec3));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        });
+
         let mut complexes = HashMap::new();
 
-        parsed_signs.iter().for_each(|sign| {
+        parsed_signs.iter().for_each(|(_source, sign)| {
             if let ParsedSign::StorageComplex {
                 dimension,
                 y_level,
                 bounds,
+                layers,
+                stride,
+                open_from,
                 name,
             } = sign
             {
@@ -567,6 +2023,9 @@ impl SignConfigState {
                         dimension: *dimension,
                         y_level: *y_level,
                         bounds: *bounds,
+                        layers: *layers,
+                        stride: *stride,
+                        open_from: *open_from,
                     },
                 );
             }
@@ -575,6 +2034,8 @@ impl SignConfigState {
                 dimension,
                 origin,
                 height,
+                radius,
+                skipped_layers,
                 name,
             } = sign
             {
@@ -585,12 +2046,41 @@ impl SignConfigState {
                         dimension: *dimension,
                         origin: *origin,
                         height: *height,
+                        radius: *radius,
+                        skipped_layers: skipped_layers.clone(),
+                    },
+                );
+            }
+
+            if let ParsedSign::StorageWall {
+                dimension,
+                origin,
+                width,
+                height,
+                facing,
+                standoff,
+                name,
+            } = sign
+            {
+                complexes.insert(
+                    name.clone(),
+                    StorageComplex::Wall {
+                        name: name.clone(),
+                        dimension: *dimension,
+                        origin: *origin,
+                        width: *width,
+                        height: *height,
+                        facing: *facing,
+                        standoff: *standoff,
                     },
                 );
             }
         });
 
+        validation_errors.extend(validate_layout(&parsed_signs, &nodes, &complexes));
+
         CompiledSignConfig {
+            generation,
             nodes,
             complexes,
             sign_parse_errors,
@@ -602,15 +2092,248 @@ impl SignConfigState {
         let mut cached_config = self.cached_config.lock().unwrap();
 
         cached_config.take();
+        self.cached_path_graph.lock().unwrap().take();
     }
 
     pub fn get_config(&self) -> Arc<CompiledSignConfig> {
         let mut cached_config = self.cached_config.lock().unwrap();
 
         if cached_config.is_none() {
-            cached_config.replace(Arc::new(self.generate_config()));
+            let mut history = self.history.lock().unwrap();
+            let config = Arc::new(self.generate_config(history.next_generation()));
+
+            history.record(config.clone());
+            cached_config.replace(config);
         }
 
         cached_config.as_ref().unwrap().clone()
     }
+
+    // Rebuilds the config if needed so the history is up to date
+    pub fn history(&self) -> MutexGuard<'_, SignConfigHistory> {
+        self.get_config();
+
+        self.history.lock().unwrap()
+    }
+
+    pub fn set_edge_penalties(&mut self, edge_penalties: HashMap<(String, String), u32>) {
+        if self.edge_penalties == edge_penalties {
+            return;
+        }
+
+        self.edge_penalties = edge_penalties;
+        self.cached_path_graph.lock().unwrap().take();
+    }
+
+    pub fn set_learned_edge_costs(&mut self, learned_edge_costs: HashMap<(String, String), u32>) {
+        if self.learned_edge_costs == learned_edge_costs {
+            return;
+        }
+
+        self.learned_edge_costs = learned_edge_costs;
+        self.cached_path_graph.lock().unwrap().take();
+    }
+
+    pub fn get_path_graph(&self) -> Arc<PathGraph> {
+        // Taken before the graph lock, set_dirty locks the config first
+        let config = self.get_config();
+        let mut cached_path_graph = self.cached_path_graph.lock().unwrap();
+
+        if cached_path_graph.is_none() {
+            cached_path_graph.replace(Arc::new(PathGraph::new(
+                config,
+                &self.edge_penalties,
+                &self.learned_edge_costs,
+                self.path_cache_metrics.clone(),
+            )));
+        }
+
+        cached_path_graph.as_ref().unwrap().clone()
+    }
+
+    pub fn path_cache_stats(&self) -> PathCacheStats {
+        let cached_paths = self
+            .cached_path_graph
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |graph| graph.cached_paths());
+
+        self.path_cache_metrics.stats(cached_paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(lines: [&str; 4]) -> Sign {
+        Sign {
+            lines: lines.map(str::to_owned),
+            location: Location {
+                vec3: Vec3 { x: 0, y: 64, z: 0 },
+                dim: Dimension::Overworld,
+            },
+            facing: None,
+        }
+    }
+
+    #[test]
+    fn parses_tower_layouts() {
+        assert_eq!(parse_tower_layout("12").unwrap(), (12, 4, vec![]));
+        assert_eq!(
+            parse_tower_layout("12 r3 skip 4,8").unwrap(),
+            (12, 3, vec![4, 8])
+        );
+        assert_eq!(parse_tower_layout("6 skip 0").unwrap(), (6, 4, vec![0]));
+    }
+
+    #[test]
+    fn rejects_malformed_tower_layouts() {
+        assert!(matches!(
+            parse_tower_layout(""),
+            Err(SignParseError::BadHeight)
+        ));
+        assert!(matches!(
+            parse_tower_layout("tall"),
+            Err(SignParseError::BadHeight)
+        ));
+        assert!(matches!(
+            parse_tower_layout("12 r0"),
+            Err(SignParseError::BadTowerLayout)
+        ));
+        assert!(matches!(
+            parse_tower_layout("12 skip"),
+            Err(SignParseError::BadTowerLayout)
+        ));
+        assert!(matches!(
+            parse_tower_layout("12 skip 4,x"),
+            Err(SignParseError::BadTowerLayout)
+        ));
+        assert!(matches!(
+            parse_tower_layout("12 wide"),
+            Err(SignParseError::BadTowerLayout)
+        ));
+    }
+
+    #[test]
+    fn parses_floor_layouts() {
+        assert_eq!(
+            parse_floor_layout("8,0,8").unwrap(),
+            (Vec3 { x: 8, y: 0, z: 8 }, 1, 0, OpenFrom::Above)
+        );
+        assert_eq!(
+            parse_floor_layout("8,0,8 3x2 below").unwrap(),
+            (Vec3 { x: 8, y: 0, z: 8 }, 3, 2, OpenFrom::Below)
+        );
+        assert_eq!(
+            parse_floor_layout("-4,0,0 north").unwrap(),
+            (
+                Vec3 { x: -4, y: 0, z: 0 },
+                1,
+                0,
+                OpenFrom::Side(Direction::North)
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_floor_layouts() {
+        for layout in [
+            "",
+            "8,0",
+            "8,0,x",
+            "8,0,8 0x2",
+            "8,0,8 3x0",
+            "8,0,8 3xtwo",
+            "8,0,8 up",
+        ] {
+            assert!(
+                matches!(
+                    parse_floor_layout(layout),
+                    Err(SignParseError::BadFloorLayout)
+                ),
+                "{layout}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_side_opened_floors_more_than_one_row_deep() {
+        let shallow = sign(["SSS", "storage complex", "0,0,8 east", "Floor"]);
+        let deep = sign(["SSS", "storage complex", "8,0,8 east", "Floor"]);
+
+        assert!(ParsedSign::try_from(&shallow).is_ok());
+        assert!(matches!(
+            ParsedSign::try_from(&deep),
+            Err(SignParseError::SideOpenedFloorTooDeep)
+        ));
+    }
+
+    #[test]
+    fn parses_wall_layouts() {
+        assert_eq!(
+            parse_wall_layout("9x4 north 2").unwrap(),
+            (9, 4, Direction::North, 2)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_wall_layouts() {
+        for layout in [
+            "",
+            "9x4 north",
+            "9x4 north 2 extra",
+            "94 north 2",
+            "9x4 up 2",
+            "0x4 north 2",
+            "9x0 north 2",
+            "9x4 north 0",
+            "9x4 north -1",
+        ] {
+            assert!(
+                matches!(
+                    parse_wall_layout(layout),
+                    Err(SignParseError::BadWallLayout)
+                ),
+                "{layout}"
+            );
+        }
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("pickup", "pickup"), 0);
+        assert_eq!(edit_distance("pikup", "pickup"), 1);
+        assert_eq!(edit_distance("path nod", "path node"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "link"), 4);
+    }
+
+    #[test]
+    fn suggests_close_sign_types() {
+        let suggest = |input| closest_match(input, SIGN_TYPES.iter().copied());
+
+        assert_eq!(suggest("path nod").as_deref(), Some("path node"));
+        assert_eq!(suggest("stoarge tower").as_deref(), Some("storage tower"));
+        assert_eq!(suggest("lnk").as_deref(), Some("link"));
+        assert_eq!(suggest("teleporter"), None);
+    }
+
+    #[test]
+    fn limits_suggestions_by_input_length() {
+        let candidates = ["Hallway A", "ab"];
+
+        // Short names allow two edits, longer ones a third of their length
+        assert_eq!(
+            closest_match("xy", candidates.iter().copied()).as_deref(),
+            Some("ab")
+        );
+        assert_eq!(
+            closest_match("Hallwya B", candidates.iter().copied()).as_deref(),
+            Some("Hallway A")
+        );
+        assert_eq!(closest_match("Hall", candidates.iter().copied()), None);
+    }
 }
@@ -0,0 +1,131 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::types::Location;
+
+use super::sign_config::{CompiledSignConfig, Portal};
+
+// Only the most recent changes are kept
+const MAX_HISTORY_LENGTH: usize = 100;
+
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct ChangeSet {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ChangeSet {
+    fn between<T: PartialEq>(previous: &HashMap<String, T>, current: &HashMap<String, T>) -> Self {
+        let mut change_set = ChangeSet::default();
+
+        for (name, item) in current.iter() {
+            match previous.get(name) {
+                None => change_set.added.push(name.clone()),
+                Some(previous_item) if previous_item != item => {
+                    change_set.changed.push(name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+
+        change_set.removed = previous
+            .keys()
+            .filter(|name| !current.contains_key(*name))
+            .cloned()
+            .collect();
+
+        change_set.added.sort();
+        change_set.removed.sort();
+        change_set.changed.sort();
+
+        change_set
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn portals(config: &CompiledSignConfig) -> HashMap<String, &Portal> {
+    config
+        .nodes
+        .iter()
+        .filter_map(|(name, node)| node.portal.as_ref().map(|portal| (name.clone(), portal)))
+        .collect()
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SignConfigChange {
+    pub generation: u64,
+    pub compiled_at: DateTime<Utc>,
+    pub nodes: ChangeSet,
+    pub complexes: ChangeSet,
+    // Keyed by the node the portal leaves from
+    pub portals: ChangeSet,
+    // Where removed nodes were, so operations still heading there can be found
+    pub removed_node_locations: HashMap<String, Location>,
+}
+
+#[derive(Default)]
+pub struct SignConfigHistory {
+    generation: u64,
+    previous_config: Option<Arc<CompiledSignConfig>>,
+    changes: VecDeque<SignConfigChange>,
+}
+
+impl SignConfigHistory {
+    pub fn next_generation(&self) -> u64 {
+        self.generation + 1
+    }
+
+    // Records a newly compiled config, keeping a change entry only if something differs
+    pub fn record(&mut self, config: Arc<CompiledSignConfig>) {
+        self.generation = config.generation;
+
+        let empty_config = CompiledSignConfig::default();
+        let previous = self.previous_config.as_deref().unwrap_or(&empty_config);
+
+        let nodes = ChangeSet::between(&previous.nodes, &config.nodes);
+        let complexes = ChangeSet::between(&previous.complexes, &config.complexes);
+        let portals = ChangeSet::between(&portals(previous), &portals(&config));
+
+        if !(nodes.is_empty() && complexes.is_empty() && portals.is_empty()) {
+            let removed_node_locations = nodes
+                .removed
+                .iter()
+                .map(|name| (name.clone(), previous.nodes.get(name).unwrap().location))
+                .collect();
+
+            self.changes.push_back(SignConfigChange {
+                generation: config.generation,
+                compiled_at: Utc::now(),
+                nodes,
+                complexes,
+                portals,
+                removed_node_locations,
+            });
+
+            if self.changes.len() > MAX_HISTORY_LENGTH {
+                self.changes.pop_front();
+            }
+        }
+
+        self.previous_config = Some(config);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SignConfigChange> {
+        self.changes.iter()
+    }
+
+    pub fn get(&self, generation: u64) -> Option<&SignConfigChange> {
+        self.changes
+            .iter()
+            .find(|change| change.generation == generation)
+    }
+}
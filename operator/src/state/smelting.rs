@@ -0,0 +1,71 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use super::operations::OperationPriority;
+use crate::types::Location;
+
+// Items waiting to be loaded into furnaces
+#[derive(Serialize, Clone, Debug)]
+pub struct SmeltJob {
+    pub id: Uuid,
+    pub input_id: u32,
+    pub result_id: u32,
+    pub remaining: u32,
+    pub priority: OperationPriority,
+    // Set once an alert has been raised for the job being stuck
+    pub alerted: bool,
+}
+
+// Items loaded into one furnace, from the Smelt operation until they have been collected
+#[derive(Serialize, Clone, Debug)]
+pub struct FurnaceLoad {
+    pub job_id: Uuid,
+    pub furnace: Location,
+    pub input_id: u32,
+    pub node_location: Location,
+    pub result_id: u32,
+    pub count: u32,
+    pub priority: OperationPriority,
+    pub smelt_operation: Uuid,
+    // Once the Smelt operation has completed
+    pub ready_at: Option<DateTime<Utc>>,
+    pub collect_operation: Option<Uuid>,
+}
+
+#[derive(Default)]
+pub struct SmeltingState {
+    pub jobs: Vec<SmeltJob>,
+    pub loads: Vec<FurnaceLoad>,
+}
+
+impl SmeltingState {
+    pub fn queue_job(
+        &mut self,
+        input_id: u32,
+        result_id: u32,
+        count: u32,
+        priority: OperationPriority,
+    ) -> &SmeltJob {
+        let id = Uuid::new_v4();
+
+        self.jobs.push(SmeltJob {
+            id,
+            input_id,
+            result_id,
+            remaining: count,
+            priority,
+            alerted: false,
+        });
+
+        // Higher priority jobs are loaded first, in the order they were queued
+        self.jobs.sort_by_key(|job| job.priority);
+
+        self.jobs.iter().find(|job| job.id == id).unwrap()
+    }
+
+    pub fn busy_furnaces(&self) -> HashSet<Location> {
+        self.loads.iter().map(|load| load.furnace).collect()
+    }
+}
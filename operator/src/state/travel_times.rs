@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{pathfinding::TravelEstimator, types::Location};
+
+use super::operations::Operation;
+
+// Weight given to each new sample in the rolling averages
+const SMOOTHING: f64 = 0.2;
+// Samples needed before an estimate is trusted over distance
+const MIN_SAMPLES: u32 = 3;
+// Walking speed of a player, assumed until agent speed has been learned
+pub const DEFAULT_BLOCKS_PER_SECOND: f64 = 4.3;
+
+#[derive(Serialize, Clone, Copy, Debug, Default)]
+pub struct RollingEstimate {
+    pub mean: f64,
+    pub samples: u32,
+}
+
+impl RollingEstimate {
+    fn record(&mut self, value: f64) {
+        self.mean = if self.samples == 0 {
+            value
+        } else {
+            self.mean + SMOOTHING * (value - self.mean)
+        };
+        self.samples += 1;
+    }
+
+    fn trusted(&self) -> Option<f64> {
+        (self.samples >= MIN_SAMPLES).then_some(self.mean)
+    }
+}
+
+#[derive(Default)]
+pub struct TravelTimeState {
+    // Seconds taken to walk between two nodes, in the direction walked
+    edge_seconds: HashMap<(String, String), RollingEstimate>,
+    // Speed agents travel at over all reported segments
+    blocks_per_second: RollingEstimate,
+    // Seconds from an operation being taken to its completion, less the expected walk to its
+    // starting location, by operation kind
+    operation_seconds: HashMap<&'static str, RollingEstimate>,
+}
+
+impl TravelTimeState {
+    pub fn record_segment(
+        &mut self,
+        source_node: &str,
+        destination_node: &str,
+        // Portal edges have no walking distance to learn agent speed from
+        distance: Option<f64>,
+        seconds: f64,
+    ) {
+        if seconds <= 0.0 {
+            return;
+        }
+
+        self.edge_seconds
+            .entry((source_node.to_owned(), destination_node.to_owned()))
+            .or_default()
+            .record(seconds);
+
+        if let Some(distance) = distance {
+            self.blocks_per_second.record(distance / seconds);
+        }
+    }
+
+    pub fn record_operation(&mut self, op: &Operation) {
+        if let (Some(started_at), Some(finalized_at), Some(travel_seconds)) =
+            (op.started_at, op.finalized_at, op.travel_seconds)
+        {
+            let seconds = (finalized_at - started_at).num_milliseconds() as f64 / 1000.0;
+
+            self.operation_seconds
+                .entry(op.kind.name())
+                .or_default()
+                .record((seconds - travel_seconds).max(0.0));
+        }
+    }
+
+    pub fn blocks_per_second(&self) -> Option<f64> {
+        self.blocks_per_second.trusted()
+    }
+
+    pub fn operation_seconds(&self, kind_name: &str) -> Option<f64> {
+        self.operation_seconds.get(kind_name)?.trusted()
+    }
+
+    // Learned edge times converted back into distance-like costs at the average agent speed, so
+    // they can replace the distance cost of an edge
+    pub fn learned_edge_costs(&self) -> HashMap<(String, String), u32> {
+        let blocks_per_second = match self.blocks_per_second() {
+            Some(blocks_per_second) => blocks_per_second,
+            None => return HashMap::new(),
+        };
+
+        self.edge_seconds
+            .iter()
+            .filter_map(|(key, estimate)| {
+                let seconds = estimate.trusted()?;
+
+                Some((
+                    key.clone(),
+                    ((seconds * blocks_per_second).round() as u32).max(1),
+                ))
+            })
+            .collect()
+    }
+
+    // Seconds an agent at the given location is expected to take to reach an operation's
+    // starting location, once agent speed has been learned
+    pub fn travel_seconds(
+        &self,
+        estimator: &TravelEstimator,
+        agent_location: Location,
+        op: &Operation,
+    ) -> Option<f64> {
+        match op.starting_location() {
+            Some(location) => Some(
+                estimator.estimate(agent_location, location) as f64 / self.blocks_per_second()?,
+            ),
+            None => Some(0.0),
+        }
+    }
+
+    // Seconds an agent at the given location is expected to take to reach and complete an
+    // operation, once agent speed has been learned
+    pub fn expected_seconds(
+        &self,
+        estimator: &TravelEstimator,
+        agent_location: Location,
+        op: &Operation,
+    ) -> Option<f64> {
+        let travel_seconds = self.travel_seconds(estimator, agent_location, op)?;
+
+        Some(travel_seconds + self.operation_seconds(op.kind.name()).unwrap_or(0.0))
+    }
+
+    pub fn iter_edges(&self) -> impl Iterator<Item = (&(String, String), &RollingEstimate)> {
+        self.edge_seconds.iter()
+    }
+
+    pub fn iter_operations(&self) -> impl Iterator<Item = (&&'static str, &RollingEstimate)> {
+        self.operation_seconds.iter()
+    }
+}
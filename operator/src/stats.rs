@@ -1,7 +1,13 @@
+use chrono::{Duration, Utc};
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use crate::{state::operations::OperationStatus, state::State};
+use crate::{
+    config::Config,
+    pathfinding::PathCacheStats,
+    state::{operations::OperationStatus, sign_config::StorageComplex, State},
+    types::Dimension,
+};
 
 #[derive(Debug, Serialize)]
 pub struct Stats {
@@ -19,6 +25,8 @@ pub struct Stats {
     pub agents_connected: usize,
 
     pub services_tick_times_micros: HashMap<&'static str, u64>,
+
+    pub path_cache: PathCacheStats,
 }
 
 pub fn calculate_stats(state: &State) -> Stats {
@@ -68,5 +76,116 @@ pub fn calculate_stats(state: &State) -> Stats {
         agents_connected,
 
         services_tick_times_micros,
+
+        path_cache: state.sign_config.path_cache_stats(),
+    }
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct CapacityStats {
+    pub containers: usize,
+    pub containers_unscanned: usize,
+    pub containers_stale: usize,
+
+    pub total_slots: usize,
+    pub free_slots: usize,
+    pub partial_stacks: usize,
+    pub distinct_items: usize,
+
+    pub average_data_age_seconds: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CapacityReport {
+    pub complexes: HashMap<String, CapacityStats>,
+    pub dimensions: HashMap<Dimension, CapacityStats>,
+}
+
+#[derive(Default)]
+struct CapacityAccumulator {
+    stats: CapacityStats,
+    item_hashes: HashSet<u64>,
+    total_data_age_seconds: i64,
+}
+
+impl CapacityAccumulator {
+    fn add_complex(&mut self, complex: &StorageComplex, state: &State, config: &Config) {
+        let now = Utc::now();
+        let stale_time = now - Duration::minutes(config.rescan_interval_minutes);
+
+        for (location, _open_from) in complex.containers() {
+            if state.inventories.canonical_location(&location) != location {
+                continue;
+            }
+
+            self.stats.containers += 1;
+
+            let inventory = match state.inventories.inventory_contents_at(&location) {
+                Some(inventory) => inventory,
+                None => {
+                    self.stats.containers_unscanned += 1;
+                    continue;
+                }
+            };
+
+            if inventory.scanned_at < stale_time {
+                self.stats.containers_stale += 1;
+            }
+
+            self.total_data_age_seconds += (now - inventory.scanned_at).num_seconds();
+
+            for item in inventory.slots.iter() {
+                self.stats.total_slots += 1;
+
+                match item {
+                    Some(item) => {
+                        if item.count < item.stack_size {
+                            self.stats.partial_stacks += 1;
+                        }
+
+                        self.item_hashes.insert(item.stackable_hash);
+                    }
+                    None => self.stats.free_slots += 1,
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> CapacityStats {
+        self.stats.distinct_items = self.item_hashes.len();
+
+        let containers_scanned = self.stats.containers - self.stats.containers_unscanned;
+        if containers_scanned > 0 {
+            self.stats.average_data_age_seconds =
+                Some(self.total_data_age_seconds / containers_scanned as i64);
+        }
+
+        self.stats
+    }
+}
+
+pub fn calculate_capacity(state: &State, config: &Config) -> CapacityReport {
+    let sign_config = state.sign_config.get_config();
+
+    let mut complexes = HashMap::new();
+    let mut dimensions: HashMap<Dimension, CapacityAccumulator> = HashMap::new();
+
+    for (name, complex) in sign_config.complexes.iter() {
+        let mut complex_acc = CapacityAccumulator::default();
+        complex_acc.add_complex(complex, state, config);
+        complexes.insert(name.clone(), complex_acc.finish());
+
+        dimensions
+            .entry(complex.dimension())
+            .or_default()
+            .add_complex(complex, state, config);
+    }
+
+    CapacityReport {
+        complexes,
+        dimensions: dimensions
+            .into_iter()
+            .map(|(dim, acc)| (dim, acc.finish()))
+            .collect(),
     }
 }
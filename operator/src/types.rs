@@ -4,7 +4,8 @@ use serde_json::Value;
 use std::cmp::{max, min};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::Hash;
-use std::ops::Add;
+use std::ops::{Add, Mul};
+use std::str::FromStr;
 use std::{fmt::Display, hash::Hasher, sync::Arc};
 use thiserror::Error;
 
@@ -31,13 +32,31 @@ pub struct Location {
     pub dim: Dimension,
 }
 
-impl Location {
-    pub fn distance_heuristic(&self, other: &Location) -> i32 {
-        if other.dim != self.dim {
-            return 1000;
+impl Dimension {
+    // Overworld blocks covered by one block in this dimension, for dimensions linked by portals
+    pub fn coordinate_scale(&self) -> Option<i32> {
+        match self {
+            Dimension::Overworld => Some(1),
+            Dimension::TheNether => Some(8),
+            Dimension::TheEnd => None,
         }
+    }
+}
 
-        self.vec3.dist(other.vec3) as i32
+impl Location {
+    // Equivalent location in another dimension, using the 8:1 nether coordinate scale
+    pub fn scaled_to(&self, dim: Dimension) -> Option<Location> {
+        let from_scale = self.dim.coordinate_scale()?;
+        let to_scale = dim.coordinate_scale()?;
+
+        Some(Location {
+            vec3: Vec3 {
+                x: self.vec3.x * from_scale / to_scale,
+                y: self.vec3.y,
+                z: self.vec3.z * from_scale / to_scale,
+            },
+            dim,
+        })
     }
 }
 
@@ -69,6 +88,62 @@ impl Display for Vec3 {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    // Unit vector pointing in this direction
+    pub fn offset(&self) -> Vec3 {
+        match self {
+            Direction::North => Vec3 { x: 0, y: 0, z: -1 },
+            Direction::East => Vec3 { x: 1, y: 0, z: 0 },
+            Direction::South => Vec3 { x: 0, y: 0, z: 1 },
+            Direction::West => Vec3 { x: -1, y: 0, z: 0 },
+        }
+    }
+
+    // The direction a quarter turn clockwise, seen from above
+    pub fn clockwise(&self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "north" => Ok(Direction::North),
+            "east" => Ok(Direction::East),
+            "south" => Ok(Direction::South),
+            "west" => Ok(Direction::West),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Mul<i32> for Vec3 {
+    type Output = Self;
+
+    fn mul(self, scale: i32) -> Self {
+        Vec3 {
+            x: self.x * scale,
+            y: self.y * scale,
+            z: self.z * scale,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Vec2 {
     pub x: i32,
@@ -115,7 +190,7 @@ pub struct UnhashedItem {
 }
 
 lazy_static::lazy_static! {
-    static ref MC_DATA: McData = McData::init();
+    pub static ref MC_DATA: McData = McData::init();
 }
 
 impl UnhashedItem {
@@ -328,6 +403,8 @@ pub enum HoldMatchError {
     AlreadyHeld,
     #[error("No match was found for the requested criteria")]
     NoMatch,
+    #[error("The requested slot does not exist in that container")]
+    InvalidSlot,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -415,13 +492,24 @@ impl HoldRequestFilter {
                 slot,
                 open_from,
             } => {
-                if state.holds.existing_hold(*location, *slot).is_some() {
+                let location = state.inventories.canonical_location(location);
+
+                let slot_exists = state
+                    .inventories
+                    .inventory_contents_at(&location)
+                    .is_some_and(|inventory| (*slot as usize) < inventory.slots.len());
+
+                if !slot_exists {
+                    return Err(HoldMatchError::InvalidSlot);
+                }
+
+                if state.holds.existing_hold(location, *slot).is_some() {
                     return Err(HoldMatchError::AlreadyHeld);
                 }
 
                 let hold = state
                     .holds
-                    .create(*location, *slot, *open_from)
+                    .create(location, *slot, *open_from)
                     .unwrap()
                     .clone();
 
@@ -431,9 +519,119 @@ impl HoldRequestFilter {
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ContainerKind {
+    Chest,
+    DoubleChest { other_half: Vec3 },
+    Barrel,
+    ShulkerBox,
+    Other { slot_count: u32 },
+}
+
+impl ContainerKind {
+    pub fn slot_count(&self) -> u32 {
+        match self {
+            ContainerKind::Chest | ContainerKind::Barrel | ContainerKind::ShulkerBox => 27,
+            ContainerKind::DoubleChest { .. } => 54,
+            ContainerKind::Other { slot_count } => *slot_count,
+        }
+    }
+
+    // Agents that don't report a kind are assumed to be opening single chests
+    pub fn from_slot_count(slot_count: usize) -> Self {
+        match slot_count {
+            27 => ContainerKind::Chest,
+            slot_count => ContainerKind::Other {
+                slot_count: slot_count as u32,
+            },
+        }
+    }
+
+    // A double chest is tracked under whichever of its halves sorts first
+    pub fn canonical_location(&self, location: Location) -> Location {
+        match self {
+            ContainerKind::DoubleChest { other_half } => {
+                let a = location.vec3;
+                let b = *other_half;
+
+                if (b.x, b.y, b.z) < (a.x, a.y, a.z) {
+                    Location {
+                        vec3: b,
+                        dim: location.dim,
+                    }
+                } else {
+                    location
+                }
+            }
+            _ => location,
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct Inventory {
+    pub kind: ContainerKind,
     pub slots: Vec<Option<Item>>,
     pub scanned_at: DateTime<Utc>,
     pub open_from: Vec3,
+    // Contents were derived from a completed operation rather than observed by an agent
+    pub predicted: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(x: i32, y: i32, z: i32) -> Location {
+        Location {
+            vec3: Vec3 { x, y, z },
+            dim: Dimension::TheNether,
+        }
+    }
+
+    #[test]
+    fn double_chests_along_x_use_the_western_half() {
+        let west = location(-5, 64, 10);
+        let east = location(-4, 64, 10);
+
+        let from_west = ContainerKind::DoubleChest {
+            other_half: east.vec3,
+        };
+        let from_east = ContainerKind::DoubleChest {
+            other_half: west.vec3,
+        };
+
+        assert_eq!(from_west.canonical_location(west), west);
+        assert_eq!(from_east.canonical_location(east), west);
+    }
+
+    #[test]
+    fn double_chests_along_z_use_the_northern_half() {
+        let north = location(3, 70, -2);
+        let south = location(3, 70, -1);
+
+        let from_south = ContainerKind::DoubleChest {
+            other_half: north.vec3,
+        };
+
+        assert_eq!(from_south.canonical_location(south), north);
+        assert_eq!(
+            from_south.canonical_location(south).dim,
+            Dimension::TheNether
+        );
+    }
+
+    #[test]
+    fn other_containers_keep_their_location() {
+        let chest = location(0, 64, 0);
+
+        for kind in [
+            ContainerKind::Chest,
+            ContainerKind::Barrel,
+            ContainerKind::ShulkerBox,
+            ContainerKind::Other { slot_count: 5 },
+        ] {
+            assert_eq!(kind.canonical_location(chest), chest);
+        }
+    }
 }